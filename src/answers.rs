@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::solution::{Answer, Part};
+use crate::toml::{self, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    Wrong(Answer),
+    Unknown,
+}
+
+/// Accepted answers per day and part, stored as a small TOML file:
+///
+/// ```toml
+/// [day1]
+/// part1 = 24000
+/// part2 = "CMZ"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    entries: BTreeMap<(u8, u8), Answer>,
+}
+
+impl Answers {
+    // A missing file is an empty store, so the first --record can create it
+    pub fn load<P>(path: P) -> Result<Self>
+        where P: AsRef<Path> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).map_err(|e| Error::io(e).in_file(path))?;
+        Self::parse(&text).map_err(|e| e.in_file(path))
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<()>
+        where P: AsRef<Path> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| Error::io(e).in_file(path))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut answers = Self::default();
+        for table in toml::parse(text)? {
+            let day = match &table.name {
+                None => None,
+                Some(name) => Some(name.strip_prefix("day")
+                    .and_then(|s| s.parse::<u8>().ok())
+                    .ok_or_else(|| Error::parse(format!("Expected a section like [day1], found [{name}]")).at(table.line, 1))?),
+            };
+            for entry in table.entries {
+                let part = match entry.key.as_str() {
+                    "part1" => 1,
+                    "part2" => 2,
+                    key => return Err(Error::parse(format!("Unknown key '{key}', expected part1 or part2")).at(entry.line, entry.column)),
+                };
+                let day = day.ok_or_else(|| Error::parse("Answer outside of a [dayN] section").at(entry.line, entry.column))?;
+                if answers.entries.insert((day, part), Answer::from(entry.value)).is_some() {
+                    return Err(Error::parse(format!("Duplicate answer for part{part} of day {day}")).at(entry.line, entry.column));
+                }
+            }
+        }
+        Ok(answers)
+    }
+
+    pub fn get(&self, day: u8, part: Part) -> Option<&Answer> {
+        self.entries.get(&(day, part.number()))
+    }
+
+    pub fn record(&mut self, day: u8, part: Part, answer: Answer) {
+        self.entries.insert((day, part.number()), answer);
+    }
+
+    pub fn check(&self, day: u8, part: Part, answer: &Answer) -> Verdict {
+        match self.get(day, part) {
+            None => Verdict::Unknown,
+            Some(expected) if expected == answer => Verdict::Correct,
+            Some(expected) => Verdict::Wrong(expected.clone()),
+        }
+    }
+}
+
+impl From<Value> for Answer {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Integer(n) => Answer::Number(n),
+            Value::String(s) => Answer::Text(s),
+            Value::Array(items) => Answer::List(items.into_iter().map(Answer::from).collect()),
+        }
+    }
+}
+
+impl From<&Answer> for Value {
+    fn from(answer: &Answer) -> Self {
+        match answer {
+            Answer::Number(n) => Value::Integer(*n),
+            Answer::Text(s) => Value::String(s.clone()),
+            Answer::List(items) => Value::Array(items.iter().map(Value::from).collect()),
+        }
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut current = None;
+        for ((day, part), answer) in &self.entries {
+            if current != Some(*day) {
+                if current.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(f, "[day{day}]")?;
+                current = Some(*day);
+            }
+            writeln!(f, "part{part} = {}", Value::from(answer))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solution::{Answer, Part};
+    use super::{Answers, Verdict};
+
+    #[test]
+    fn test_round_trip() {
+        let text = "\
+# accepted answers
+[day1]
+part1 = 24_000 # sample
+part2 = \"CMZ\"
+
+[ day6 ]
+part1 = [7, \"a#\\\"b\"]
+";
+        let answers = Answers::parse(text).unwrap();
+        assert_eq!(answers.get(1, Part::One), Some(&Answer::Number(24_000)));
+        assert_eq!(answers.get(1, Part::Two), Some(&Answer::Text("CMZ".to_string())));
+        let list = Answer::List(vec![Answer::Number(7), Answer::Text("a#\"b".to_string())]);
+        assert_eq!(answers.get(6, Part::One), Some(&list));
+        assert_eq!(answers.get(6, Part::Two), None);
+        assert_eq!(Answers::parse(&answers.to_string()).unwrap(), answers);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut answers = Answers::default();
+        answers.record(2, Part::One, Answer::Number(15));
+        assert_eq!(answers.check(2, Part::One, &Answer::Number(15)), Verdict::Correct);
+        assert_eq!(answers.check(2, Part::One, &Answer::Number(16)), Verdict::Wrong(Answer::Number(15)));
+        assert_eq!(answers.check(2, Part::Two, &Answer::Number(12)), Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = Answers::parse("[day1]\npart1 = 12 3").unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(12)));
+        let error = Answers::parse("part1 = 1").unwrap_err();
+        assert_eq!(error.line(), Some(1));
+        let error = Answers::parse("[day1]\npart3 = 1").unwrap_err();
+        assert_eq!(error.line(), Some(2));
+        let error = Answers::parse("[day1]\npart1 = 1\npart2 = 2\npart1 = 3").unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(4), Some(1)));
+        assert_eq!(error.reason(), "Duplicate answer for part1 of day 1");
+    }
+}
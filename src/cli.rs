@@ -0,0 +1,404 @@
+use std::fmt;
+use std::num::NonZeroU32;
+use std::process::ExitCode;
+
+use aoc23::error::Error;
+use aoc23::input::{InputSource, INPUT_DIR_VAR};
+use aoc23::solution::Part;
+
+use crate::output::Format;
+
+pub(crate) const USAGE: &str = "\
+Usage: aoc23 [bench] [DAYS]... [--part 1|2] [--time] [--stream] [--jobs N] [--runs N] [--check] [--record]
+             [--answers PATH] [--format text|json|ndjson] [--input [DAY=]PATH|-] [--inline [DAY=]TEXT]
+             [--rules PATH]
+       aoc23 report [--csv] [--above CALORIES] [--bucket CALORIES] [--input PATH|-] [--inline TEXT]
+       aoc23 tournament [--csv] [--format text|json|ndjson] [--rules PATH] [--input PATH|-] [--inline TEXT]
+       aoc23 coverage [--format text|json] [--input PATH|-] [--inline TEXT]
+       aoc23 plan [--csv] [--format text|json] [--input PATH|-] [--inline TEXT]
+
+  bench    repeat parsing and each part, reporting min, median and p99 wall times
+  report   rank the elves of day 1 by calories and print statistics about their inventory
+  tournament
+           compare both readings of the strategy guide of day 2 round by round, with the
+           expected score of every move and the best response to the opponent
+  coverage analyze which sections the cleaning assignments of day 4 cover, once or more often,
+           and which elves could be released without uncovering any section; every line is a
+           group of any size, with negative (-5--2), single (5) and open (5-, -9) ranges
+  plan     reorganize the rucksacks of day 3 so no item is left in both compartments, with the
+           moves and their cost in priorities for every rucksack
+  DAYS     a single day (5), an inclusive range (3..=6) or an exclusive range (3..6);
+           defaults to every registered day
+  --part   only run the given part of each selected day
+  --time   print the wall time of parsing and of each part
+  --stream solve days that support it in a single pass without keeping the input in memory,
+           for inputs larger than memory; the pass is timed as parsing
+  --jobs   solve up to N days at once, each with its parts in parallel; results are still
+           printed in order. Not available in bench mode (default 1)
+  --runs   number of repetitions per phase in bench mode (default 100)
+  --check  compare every answer with the answers file and mark it correct, wrong or unknown
+  --record like --check, but store answers that are not known yet in the answers file
+  --answers
+           answers file to check against (default resources/answers.toml)
+  --format print prose (text, the default), one JSON array of results (json)
+           or one JSON object per line as soon as it is known (ndjson)
+  --input  read the input from PATH, or from stdin for '-'; prefixed with DAY= it only
+           applies to that day. Defaults to dayN.txt in $AOC_INPUT_DIR or resources/
+  --inline use TEXT itself as the input, optionally only for DAY
+  --rules  decode and score the strategy guide of day 2 with the rules file at PATH
+  --csv    print the ranking of the report, the rounds of the tournament or the layouts of
+           the plan as CSV instead of a table with statistics
+  --above  also count the elves in the report that carry more than CALORIES
+  --bucket width of the histogram buckets in the report (default 10000)
+  --help   print this message";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Run,
+    Bench,
+    Report,
+    Tournament,
+    Coverage,
+    Plan,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Args {
+    pub(crate) mode: Mode,
+    pub(crate) days: Vec<u8>,
+    pub(crate) parts: Vec<Part>,
+    pub(crate) time: bool,
+    pub(crate) stream: bool,
+    pub(crate) jobs: usize,
+    pub(crate) runs: usize,
+    pub(crate) check: bool,
+    pub(crate) record: bool,
+    pub(crate) answers: String,
+    pub(crate) format: Format,
+    pub(crate) inputs: Vec<(Option<u8>, InputSource)>,
+    pub(crate) rules: Option<String>,
+    pub(crate) csv: bool,
+    pub(crate) above: Option<u32>,
+    pub(crate) bucket: NonZeroU32,
+}
+
+#[derive(Debug)]
+pub(crate) enum Failure {
+    Help,
+    Usage(String),
+    UnknownDay(u8),
+    InputMissing(u8, Error),
+    SolverFailed(u8, Error),
+    WrongAnswer(u8, Part),
+    AnswersFile(Error),
+    RulesFile(Error),
+}
+
+impl Failure {
+    pub(crate) fn exit_code(&self) -> ExitCode {
+        match self {
+            Failure::Help => ExitCode::SUCCESS,
+            Failure::Usage(_) => ExitCode::from(1),
+            Failure::UnknownDay(_) => ExitCode::from(2),
+            Failure::InputMissing(..) | Failure::AnswersFile(_) | Failure::RulesFile(_) => ExitCode::from(3),
+            Failure::SolverFailed(..) => ExitCode::from(4),
+            Failure::WrongAnswer(..) => ExitCode::from(5),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Help => write!(f, "{USAGE}"),
+            Failure::Usage(message) => write!(f, "{message}\n\n{USAGE}"),
+            Failure::UnknownDay(day) => write!(f, "Day {day} is not implemented"),
+            Failure::InputMissing(day, e) => write!(f, "Day {day}: could not read input: {e} (use --input or ${INPUT_DIR_VAR} to point to it)"),
+            Failure::SolverFailed(day, e) => write!(f, "Day {day}: solver failed: {e}"),
+            Failure::WrongAnswer(day, part) => write!(f, "Day {day}: wrong answer for {part}"),
+            Failure::AnswersFile(e) => write!(f, "Could not use answers file: {e}"),
+            Failure::RulesFile(e) => write!(f, "Could not use rules file: {e}"),
+        }
+    }
+}
+
+fn parse_day(s: &str) -> Result<u8, Failure> {
+    s.parse().map_err(|_| Failure::Usage(format!("Invalid day '{s}'")))
+}
+
+fn parse_days(s: &str) -> Result<Vec<u8>, Failure> {
+    let days = if let Some((start, end)) = s.split_once("..=") {
+        (parse_day(start)?..=parse_day(end)?).collect()
+    } else if let Some((start, end)) = s.split_once("..") {
+        (parse_day(start)?..parse_day(end)?).collect()
+    } else {
+        vec![parse_day(s)?]
+    };
+    // an empty range would otherwise select every day, like giving no days at all
+    if days.is_empty() {
+        return Err(Failure::Usage(format!("The range '{s}' contains no days")));
+    }
+    Ok(days)
+}
+
+fn parse_part(s: &str) -> Result<Part, Failure> {
+    match s {
+        "1" => Ok(Part::One),
+        "2" => Ok(Part::Two),
+        _ => Err(Failure::Usage(format!("Invalid part '{s}', expected 1 or 2"))),
+    }
+}
+
+fn parse_format(s: &str) -> Result<Format, Failure> {
+    match s {
+        "text" => Ok(Format::Text),
+        "json" => Ok(Format::Json),
+        "ndjson" => Ok(Format::Ndjson),
+        _ => Err(Failure::Usage(format!("Invalid format '{s}', expected text, json or ndjson"))),
+    }
+}
+
+// Splits an optional `DAY=` prefix off an input argument
+fn parse_input(s: &str) -> (Option<u8>, &str) {
+    match s.split_once('=') {
+        Some((day, rest)) => match day.parse() {
+            Ok(day) => (Some(day), rest),
+            Err(_) => (None, s),
+        },
+        None => (None, s),
+    }
+}
+
+fn parse_calories(s: &str) -> Result<u32, Failure> {
+    s.replace('_', "").parse().map_err(|_| Failure::Usage(format!("Invalid number of calories '{s}'")))
+}
+
+fn parse_count(s: &str, what: &str) -> Result<usize, Failure> {
+    match s.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(Failure::Usage(format!("Invalid number of {what} '{s}'"))),
+    }
+}
+
+impl Args {
+    pub(crate) fn parse<I>(args: I) -> Result<Self, Failure>
+        where I: IntoIterator<Item=String> {
+        let mut args = args.into_iter().peekable();
+        let mut result = Self {
+            mode: Mode::Run,
+            days: vec![],
+            parts: vec![Part::One, Part::Two],
+            time: false,
+            stream: false,
+            jobs: 1,
+            runs: 100,
+            check: false,
+            record: false,
+            answers: "resources/answers.toml".to_string(),
+            format: Format::Text,
+            inputs: vec![],
+            rules: None,
+            csv: false,
+            above: None,
+            bucket: NonZeroU32::new(10_000).unwrap(),
+        };
+        match args.next_if(|arg| ["bench", "report", "tournament", "coverage", "plan"].contains(&arg.as_str())).as_deref() {
+            Some("bench") => result.mode = Mode::Bench,
+            Some("report") => result.mode = Mode::Report,
+            Some("tournament") => result.mode = Mode::Tournament,
+            Some("coverage") => result.mode = Mode::Coverage,
+            Some(_) => result.mode = Mode::Plan,
+            None => {}
+        }
+        while let Some(arg) = args.next() {
+            // Options take their value either as the next argument or inline after a '='
+            let (option, inline) = match arg.split_once('=') {
+                Some((option, value)) if arg.starts_with("--") => (option, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || inline.clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| Failure::Usage(format!("Missing value for {option}")));
+            match option {
+                "-h" | "--help" => return Err(Failure::Help),
+                "-p" | "--part" => result.parts = vec![parse_part(&value()?)?],
+                "-t" | "--time" => result.time = true,
+                "--stream" => result.stream = true,
+                "-j" | "--jobs" => result.jobs = parse_count(&value()?, "jobs")?,
+                "--runs" => result.runs = parse_count(&value()?, "runs")?,
+                "--check" => result.check = true,
+                "--record" => result.record = true,
+                "--answers" => result.answers = value()?,
+                "-f" | "--format" => result.format = parse_format(&value()?)?,
+                "-i" | "--input" => {
+                    let value = value()?;
+                    let (day, path) = parse_input(&value);
+                    let source = match path {
+                        "-" => InputSource::Stdin,
+                        path => InputSource::Path(path.into()),
+                    };
+                    result.inputs.push((day, source));
+                }
+                "--inline" => {
+                    let value = value()?;
+                    let (day, text) = parse_input(&value);
+                    result.inputs.push((day, InputSource::Inline(text.to_string())));
+                }
+                "--rules" => result.rules = Some(value()?),
+                "--csv" => result.csv = true,
+                "--above" => result.above = Some(parse_calories(&value()?)?),
+                "--bucket" => result.bucket = NonZeroU32::new(parse_calories(&value()?)?)
+                    .ok_or_else(|| Failure::Usage("Buckets must be at least one calorie wide".to_string()))?,
+                _ if arg.starts_with('-') => return Err(Failure::Usage(format!("Unknown option '{arg}'"))),
+                _ => result.days.append(&mut parse_days(&arg)?),
+            }
+        }
+        // parallel runs would skew the timings
+        if result.mode == Mode::Bench && result.jobs > 1 {
+            return Err(Failure::Usage("--jobs cannot be used in bench mode".to_string()));
+        }
+        // the report prints text or CSV only, the coverage analysis and the plan one JSON object
+        match (result.mode, result.format) {
+            (Mode::Report, Format::Json | Format::Ndjson) => {
+                return Err(Failure::Usage("The report cannot be printed as JSON, use --csv".to_string()));
+            }
+            (Mode::Coverage | Mode::Plan, Format::Ndjson) => {
+                return Err(Failure::Usage("--format ndjson cannot be used in coverage or plan mode".to_string()));
+            }
+            _ => {}
+        }
+        if result.mode == Mode::Report && !result.days.is_empty() {
+            return Err(Failure::Usage("The report always covers day 1".to_string()));
+        }
+        if result.mode == Mode::Tournament && !result.days.is_empty() {
+            return Err(Failure::Usage("The tournament always covers day 2".to_string()));
+        }
+        if result.mode == Mode::Coverage && !result.days.is_empty() {
+            return Err(Failure::Usage("The coverage analysis always covers day 4".to_string()));
+        }
+        if result.mode == Mode::Plan && !result.days.is_empty() {
+            return Err(Failure::Usage("The reorganization plan always covers day 3".to_string()));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc23::input::InputSource;
+    use aoc23::solution::Part;
+    use crate::output::Format;
+    use super::{Args, Failure, Mode};
+
+    fn parse(args: &[&str]) -> Result<Args, Failure> {
+        Args::parse(args.iter().map(<&str>::to_string))
+    }
+
+    #[test]
+    fn test_days() {
+        assert_eq!(parse(&[]).unwrap().days, vec![]);
+        assert_eq!(parse(&["5"]).unwrap().days, vec![5]);
+        assert_eq!(parse(&["3..=6"]).unwrap().days, vec![3, 4, 5, 6]);
+        assert_eq!(parse(&["3..6", "1"]).unwrap().days, vec![3, 4, 5, 1]);
+    }
+
+    #[test]
+    fn test_parts() {
+        let args = parse(&["7", "--part", "2"]).unwrap();
+        assert_eq!(args.days, vec![7]);
+        assert_eq!(args.parts, vec![Part::Two]);
+        assert_eq!(parse(&["--part=1"]).unwrap().parts, vec![Part::One]);
+        assert!(matches!(parse(&["--part", "3"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_bench() {
+        let args = parse(&["bench", "3", "--runs", "10", "--time"]).unwrap();
+        assert_eq!(args.mode, Mode::Bench);
+        assert_eq!(args.days, vec![3]);
+        assert_eq!(args.runs, 10);
+        assert!(args.time);
+        assert_eq!(parse(&["3"]).unwrap().mode, Mode::Run);
+        assert!(parse(&["2", "--stream"]).unwrap().stream);
+        assert_eq!(parse(&["-j", "4"]).unwrap().jobs, 4);
+        assert!(matches!(parse(&["bench", "--jobs=2"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["--runs=0"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_report() {
+        let args = parse(&["report", "--csv", "--above", "50_000", "--bucket=5000"]).unwrap();
+        assert_eq!(args.mode, Mode::Report);
+        assert!(args.csv);
+        assert_eq!(args.above, Some(50_000));
+        assert_eq!(args.bucket.get(), 5000);
+        assert_eq!(parse(&["report"]).unwrap().bucket.get(), 10_000);
+        assert!(matches!(parse(&["report", "--bucket", "0"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["report", "2"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["report", "--format", "json"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_tournament() {
+        let args = parse(&["tournament", "--csv", "--rules", "rules.toml"]).unwrap();
+        assert_eq!(args.mode, Mode::Tournament);
+        assert!(args.csv);
+        assert_eq!(args.rules.as_deref(), Some("rules.toml"));
+        assert_eq!(parse(&["tournament", "--format=json"]).unwrap().format, Format::Json);
+        assert!(matches!(parse(&["tournament", "1"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let args = parse(&["coverage", "--format", "json"]).unwrap();
+        assert_eq!((args.mode, args.format), (Mode::Coverage, Format::Json));
+        assert!(matches!(parse(&["coverage", "4"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["coverage", "--format=ndjson"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_plan() {
+        let args = parse(&["plan", "--csv", "--inline", "aA"]).unwrap();
+        assert_eq!(args.mode, Mode::Plan);
+        assert!(args.csv);
+        assert!(matches!(parse(&["plan", "3"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["plan", "--format", "ndjson"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_check() {
+        let args = parse(&["--check", "--answers=answers.toml"]).unwrap();
+        assert!(args.check && !args.record);
+        assert_eq!(args.answers, "answers.toml");
+        assert_eq!(parse(&["--record"]).unwrap().answers, "resources/answers.toml");
+        assert_eq!(parse(&["2", "--rules", "rpsls.toml"]).unwrap().rules.as_deref(), Some("rpsls.toml"));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(parse(&[]).unwrap().format, Format::Text);
+        assert_eq!(parse(&["--format", "json"]).unwrap().format, Format::Json);
+        assert_eq!(parse(&["--format=ndjson"]).unwrap().format, Format::Ndjson);
+        assert!(matches!(parse(&["--format", "xml"]), Err(Failure::Usage(_))));
+    }
+
+    #[test]
+    fn test_inputs() {
+        let args = parse(&["--input", "5=day5.txt", "-i", "-", "--inline=2=A Y", "--input=a=b.txt"]).unwrap();
+        assert_eq!(args.inputs, vec![
+            (Some(5), InputSource::Path("day5.txt".into())),
+            (None, InputSource::Stdin),
+            (Some(2), InputSource::Inline("A Y".to_string())),
+            (None, InputSource::Path("a=b.txt".into())),
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_day() {
+        assert!(matches!(parse(&["x"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["1..=y"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["6..3"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["5..5"]), Err(Failure::Usage(_))));
+        assert!(matches!(parse(&["6..=5"]), Err(Failure::Usage(_))));
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use aoc23::day4::{Assignment, Camp};
+use aoc23::interval::{Interval, IntervalSet};
+use aoc23::json::Json;
+
+use crate::cli::Args;
+use crate::output::Format;
+
+fn count(count: u128, one: &str, many: &str) -> String {
+    match count {
+        1 => format!("1 {one}"),
+        _ => format!("{count} {many}"),
+    }
+}
+
+fn intervals_text(set: &IntervalSet<i64>) -> String {
+    if set.is_empty() {
+        return "none".to_string();
+    }
+    let intervals = set.intervals().iter()
+        .map(Interval::to_string)
+        .collect::<Vec<String>>();
+    format!("{} ({})", intervals.join(", "), count(set.covered(), "section", "sections"))
+}
+
+fn intervals_json(set: &IntervalSet<i64>) -> Json {
+    let intervals = set.intervals().iter()
+        .map(|interval| Json::Array(vec![Json::Signed(interval.start()), Json::Signed(interval.end())]))
+        .collect();
+    Json::Array(intervals)
+}
+
+fn assignment_json(assignment: &Assignment) -> Json {
+    Json::object([
+        ("line", Json::Integer(assignment.line as u64)),
+        ("elf", Json::Integer(assignment.position as u64)),
+        ("start", Json::Signed(*assignment.sections.start())),
+        ("end", Json::Signed(*assignment.sections.end())),
+    ])
+}
+
+fn print_table(camp: &Camp, assignments: &[&Assignment], kept: &HashSet<&Assignment>) {
+    println!("{:>6} {:>4} {:>13} {:>9}", "Line", "Elf", "Sections", "Status");
+    for assignment in assignments {
+        let sections = format!("{}-{}", assignment.sections.start(), assignment.sections.end());
+        let status = if kept.contains(assignment) { "kept" } else { "released" };
+        println!("{:>6} {:>4} {sections:>13} {status:>9}", assignment.line, assignment.position);
+    }
+    println!();
+    println!("Assignments:            {}", assignments.len());
+    println!("Covered:                {}", intervals_text(camp.covered()));
+    println!("Covered more than once: {}", intervals_text(&camp.multiply_covered()));
+    println!("Not covered:            {}", intervals_text(&camp.uncovered()));
+    println!("Minimal cover:          {}", count(kept.len() as u128, "assignment", "assignments"));
+    println!("Releasable:             {}", count((assignments.len() - kept.len()) as u128, "elf", "elves"));
+}
+
+fn print_json(camp: &Camp, assignments: &[&Assignment]) {
+    let json = Json::object([
+        ("assignments", Json::Integer(assignments.len() as u64)),
+        ("covered", intervals_json(camp.covered())),
+        ("multiply_covered", intervals_json(&camp.multiply_covered())),
+        ("uncovered", intervals_json(&camp.uncovered())),
+        ("minimal_cover", Json::Array(camp.minimal_cover().into_iter().map(assignment_json).collect())),
+        ("releasable", Json::Array(camp.releasable().into_iter().map(assignment_json).collect())),
+    ]);
+    println!("{json}");
+}
+
+// Analyzes the coverage of the day 4 camp, either as a table followed by a summary or as JSON
+pub(crate) fn print(camp: &Camp, args: &Args) {
+    let mut assignments = camp.assignments().collect::<Vec<&Assignment>>();
+    assignments.sort_by_key(|assignment| (assignment.line, assignment.position));
+    match args.format {
+        Format::Text => {
+            let kept = camp.minimal_cover().into_iter().collect::<HashSet<&Assignment>>();
+            print_table(camp, &assignments, &kept);
+        }
+        Format::Json | Format::Ndjson => print_json(camp, &assignments),
+    }
+}
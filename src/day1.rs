@@ -1,48 +1,298 @@
-use crate::lines;
-
-fn get_elves(lines: &Vec<String>) -> Vec<u32> {
-    let split: Vec<&[String]> = lines.split(|e| e.is_empty()).collect();
-    let mut elves = split.into_iter()
-        .map(|list| list.into_iter()
-            .filter_map(|e| e.parse::<u32>().ok())
-            .collect::<Vec<u32>>()
-        )
-        .map(|c| c.into_iter().sum())
-        .collect::<Vec<u32>>();
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::num::NonZeroU32;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::lines::{self, LineSource, Paragraph, SliceLines};
+use crate::solution::{Answer, Solution};
+use crate::stats;
+
+// Calls `f` with the items and the calorie total of every elf as soon as its group of lines ends
+fn fold_elves<F>(lines: &mut dyn LineSource, mut f: F) -> Result<()>
+    where F: FnMut(&[u32], u32) {
+    let mut items = vec![];
+    let mut total = 0u32;
+    lines::try_for_each_paragraph(lines, |paragraph| {
+        match paragraph {
+            Paragraph::Line(i, line) => {
+                let calories = line.trim().parse::<u32>()
+                    .map_err(|e| Error::parse(format!("Invalid calories '{line}': {e}")).at(i, 1))?;
+                total = total.checked_add(calories)
+                    .ok_or_else(|| Error::parse("Calorie total does not fit into u32").at(i, 1))?;
+                items.push(calories);
+            }
+            Paragraph::End => {
+                f(&items, total);
+                items.clear();
+                total = 0;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+pub fn get_elves(lines: &[String]) -> Result<Vec<u32>> {
+    read_elves(&mut SliceLines::new(lines))
+}
+
+pub fn read_elves(lines: &mut dyn LineSource) -> Result<Vec<u32>> {
+    let mut elves = vec![];
+    fold_elves(lines, |_, total| elves.push(total))?;
     elves.sort();
     elves.reverse();
-    elves
+    Ok(elves)
+}
+
+/// Fewer elves than the number of top elves that were asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotEnoughElves {
+    pub requested: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for NotEnoughElves {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expected at least {} elves, found {}", self.requested, self.found)
+    }
+}
+
+impl std::error::Error for NotEnoughElves {}
+
+impl From<NotEnoughElves> for Error {
+    fn from(error: NotEnoughElves) -> Self {
+        Error::typed(ErrorKind::Solve, error)
+    }
+}
+
+fn sum(totals: &[u32]) -> Result<u32> {
+    totals.iter()
+        .try_fold(0u32, |accu, total| accu.checked_add(*total))
+        .ok_or_else(|| Error::solve("Sum of calories does not fit into u32"))
+}
+
+/// The `k` largest calorie totals of a stream of elves, kept in a min-heap of size `k`,
+/// so ranking n elves takes O(n log k) time and O(k) memory.
+#[derive(Debug, Clone)]
+pub struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<u32>>,
+    seen: usize,
+}
+
+impl TopK {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+            seen: 0,
+        }
+    }
+
+    pub fn push(&mut self, total: u32) {
+        self.seen += 1;
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(total));
+        } else if self.heap.peek().is_some_and(|Reverse(min)| *min < total) {
+            self.heap.pop();
+            self.heap.push(Reverse(total));
+        }
+    }
+
+    // Number of elves pushed so far, including those that did not make it into the top
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    // The kept totals, largest first
+    pub fn top(&self) -> Vec<u32> {
+        let mut top = self.heap.iter()
+            .map(|Reverse(total)| *total)
+            .collect::<Vec<u32>>();
+        top.sort_by(|a, b| b.cmp(a));
+        top
+    }
+
+    // Sum of the `n` largest totals, where `n` must not exceed `k`
+    pub fn sum(&self, n: usize) -> Result<u32> {
+        if n > self.k {
+            return Err(Error::solve(format!("Only the top {} elves are kept, not {n}", self.k)));
+        }
+        if self.seen < n {
+            return Err(NotEnoughElves { requested: n, found: self.seen }.into());
+        }
+        sum(&self.top()[..n])
+    }
+}
+
+pub fn top_k_elves(lines: &mut dyn LineSource, k: usize) -> Result<TopK> {
+    let mut top = TopK::new(k);
+    fold_elves(lines, |_, total| top.push(total))?;
+    Ok(top)
+}
+
+/// One elf of the inventory, numbered from 1 in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elf {
+    index: usize,
+    items: Vec<u32>,
+    total: u32,
+}
+
+impl Elf {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn items(&self) -> &[u32] {
+        &self.items
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    pub start: u32,
+    pub count: usize,
 }
 
-fn top_n_elves(elves: &Vec<u32>, n: usize) -> u32 {
-    elves[0..n].iter()
-        .fold(0u32, |accu, item| accu + *item)
+/// Every elf with what it carries, for the statistics the planning team asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    elves: Vec<Elf>,
+    // totals in ascending order for the order statistics
+    sorted: Vec<u32>,
 }
 
-fn calculate1(elves: &Vec<u32>) -> u32 {
+impl Inventory {
+    pub fn read(lines: &mut dyn LineSource) -> Result<Self> {
+        let mut elves = vec![];
+        fold_elves(lines, |items, total| elves.push(Elf {
+            index: elves.len() + 1,
+            items: items.to_vec(),
+            total,
+        }))?;
+        let mut sorted = elves.iter()
+            .map(Elf::total)
+            .collect::<Vec<u32>>();
+        sorted.sort();
+        Ok(Self {
+            elves,
+            sorted,
+        })
+    }
+
+    pub fn elves(&self) -> &[Elf] {
+        &self.elves
+    }
+
+    // Most calories first; tied elves keep their input order
+    pub fn ranked(&self) -> Vec<&Elf> {
+        let mut ranked = self.elves.iter().collect::<Vec<&Elf>>();
+        ranked.sort_by_key(|elf| Reverse(elf.total));
+        ranked
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.sorted.is_empty() {
+            return None;
+        }
+        let sum = self.sorted.iter().map(|total| u64::from(*total)).sum::<u64>();
+        Some(sum as f64 / self.sorted.len() as f64)
+    }
+
+    // The middle total, or the mean of both middle totals for an even number of elves
+    pub fn median(&self) -> Option<f64> {
+        let n = self.sorted.len();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.sorted[n / 2].into()),
+            _ => Some((f64::from(self.sorted[n / 2 - 1]) + f64::from(self.sorted[n / 2])) / 2.0),
+        }
+    }
+
+    // Nearest rank percentile of the totals, see `stats::percentile`
+    pub fn percentile(&self, p: f64) -> Option<u32> {
+        stats::percentile(&self.sorted, p)
+    }
+
+    // Counts per `width` calories, in order of the buckets and leaving out empty ones, so that
+    // narrow buckets over widely spread totals stay as many as the elves at most
+    pub fn histogram(&self, width: NonZeroU32) -> Vec<Bucket> {
+        let width = width.get();
+        self.sorted
+            .chunk_by(|a, b| a / width == b / width)
+            .map(|totals| Bucket { start: totals[0] / width * width, count: totals.len() })
+            .collect()
+    }
+
+    // Groups of elves that carry exactly the same total, largest total first
+    pub fn ties(&self) -> Vec<Vec<&Elf>> {
+        self.ranked()
+            .chunk_by(|a, b| a.total == b.total)
+            .filter(|group| group.len() > 1)
+            .map(<[&Elf]>::to_vec)
+            .collect()
+    }
+
+    pub fn count_above(&self, calories: u32) -> usize {
+        self.sorted.len() - self.sorted.partition_point(|total| *total <= calories)
+    }
+}
+
+pub fn top_n_elves(elves: &[u32], n: usize) -> Result<u32> {
+    if elves.len() < n {
+        return Err(NotEnoughElves { requested: n, found: elves.len() }.into());
+    }
+    sum(&elves[0..n])
+}
+
+pub fn calculate1(elves: &[u32]) -> Result<u32> {
     top_n_elves(elves, 1)
 }
 
-fn calculate2(elves: &Vec<u32>) -> u32 {
+pub fn calculate2(elves: &[u32]) -> Result<u32> {
     top_n_elves(elves, 3)
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day1.txt");
-    if let Ok(lines) = lines {
-        let elves = get_elves(&lines);
-        println!("Day 1: The Elf with the most calories = {:?}", calculate1(&elves));
-        println!("Day 1: The 3 Elves with the most calories = {:?}", calculate2(&elves));
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = TopK;
+
+    const PART1: &'static str = "The Elf with the most calories";
+    const PART2: &'static str = "The 3 Elves with the most calories";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        top_k_elves(lines, 3)
+    }
+
+    fn part1(&self, top: &Self::Input) -> Result<Answer> {
+        top.sum(1).map(Answer::from)
+    }
+
+    fn part2(&self, top: &Self::Input) -> Result<Answer> {
+        top.sum(3).map(Answer::from)
+    }
+
+    // Parsing already keeps nothing but the top three
+    fn stream(&self, lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>> {
+        Some(top_k_elves(lines, 3).and_then(|top| Ok((self.part1(&top)?, self.part2(&top)?))))
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculate1, calculate2, get_elves};
+    use crate::lines::SliceLines;
+    use std::num::NonZeroU32;
+    use super::{calculate1, calculate2, get_elves, top_k_elves, Bucket, Inventory, NotEnoughElves};
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "1000",
             "2000",
             "3000",
@@ -62,16 +312,76 @@ mod tests {
     #[test]
     fn test1() {
         let lines = test_data();
-        let elves = get_elves(&lines);
-        let score = calculate1(&elves);
+        let elves = get_elves(&lines).unwrap();
+        let score = calculate1(&elves).unwrap();
         assert_eq!(score, 24_000);
     }
 
     #[test]
     fn test2() {
         let lines = test_data();
-        let elves = get_elves(&lines);
-        let score = calculate2(&elves);
+        let elves = get_elves(&lines).unwrap();
+        let score = calculate2(&elves).unwrap();
         assert_eq!(score, 45_000);
     }
+
+    #[test]
+    fn test_errors() {
+        let lines = ["1000", "", "2x00"].map(str::to_string);
+        let error = get_elves(&lines).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(3), Some(1)));
+        let elves = get_elves(&lines[..2]).unwrap();
+        assert!(calculate2(&elves).is_err());
+        let lines = ["1000\r", "2000", " \t", "", "3000"].map(str::to_string);
+        assert_eq!(get_elves(&lines).unwrap(), vec![3000, 3000]);
+    }
+
+    #[test]
+    fn test_top_k() {
+        let lines = test_data();
+        let top = top_k_elves(&mut SliceLines::new(&lines), 3).unwrap();
+        assert_eq!(top.seen(), 5);
+        assert_eq!(top.top(), vec![24_000, 11_000, 10_000]);
+        assert_eq!(top.sum(1).unwrap(), 24_000);
+        assert_eq!(top.sum(3).unwrap(), 45_000);
+        assert!(top.sum(4).is_err());
+
+        let top = top_k_elves(&mut SliceLines::new(&lines[..5]), 3).unwrap();
+        let error = top.sum(3).unwrap_err();
+        assert_eq!(error.downcast_ref(), Some(&NotEnoughElves { requested: 3, found: 2 }));
+    }
+
+    #[test]
+    fn test_inventory() {
+        let mut lines = test_data();
+        lines.extend(["", "4000"].map(str::to_string));
+        let inventory = Inventory::read(&mut SliceLines::new(&lines)).unwrap();
+        assert_eq!(inventory.elves()[2].items(), &[5000, 6000]);
+        let ranked = inventory.ranked().iter()
+            .map(|elf| (elf.index(), elf.total()))
+            .collect::<Vec<(usize, u32)>>();
+        assert_eq!(ranked, vec![(4, 24_000), (3, 11_000), (5, 10_000), (1, 6000), (2, 4000), (6, 4000)]);
+        assert_eq!(inventory.mean(), Some(59_000.0 / 6.0));
+        assert_eq!(inventory.median(), Some(8000.0));
+        assert_eq!(inventory.percentile(50.0), Some(6000));
+        assert_eq!(inventory.percentile(100.0), Some(24_000));
+        let ties = inventory.ties().iter()
+            .map(|group| group.iter().map(|elf| elf.index()).collect())
+            .collect::<Vec<Vec<usize>>>();
+        assert_eq!(ties, vec![vec![2, 6]]);
+        assert_eq!(inventory.count_above(10_000), 2);
+        assert_eq!(inventory.count_above(3999), 6);
+        let histogram = inventory.histogram(NonZeroU32::new(10_000).unwrap());
+        assert_eq!(histogram, vec![
+            Bucket { start: 0, count: 3 },
+            Bucket { start: 10_000, count: 2 },
+            Bucket { start: 20_000, count: 1 },
+        ]);
+        let empty = Inventory::read(&mut SliceLines::new(&[])).unwrap();
+        assert_eq!((empty.mean(), empty.median(), empty.percentile(90.0)), (None, None, None));
+        assert!(empty.histogram(NonZeroU32::new(1).unwrap()).is_empty());
+        let histogram = inventory.histogram(NonZeroU32::new(1).unwrap());
+        assert_eq!(histogram.len(), 5);
+        assert_eq!(histogram[0], Bucket { start: 4000, count: 2 });
+    }
 }
@@ -1,68 +1,160 @@
-use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
 use crate::day2::Hand::{Paper, Rock, Scissors};
 use crate::day2::Outcome::{Draw, Lose, Win};
-use crate::lines;
+use crate::error::{Error, Result};
+use crate::lines::{self, LineSource};
+use crate::solution::{Answer, Solution};
+use crate::toml::{self, Entry, Value};
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum Hand {
-    Rock,
-    Paper,
-    Scissors,
+/// Moves of a cyclic dominance game, where a rule table decides who beats whom instead of an
+/// ordering: Rock-Paper-Scissors, Rock-Paper-Scissors-Lizard-Spock or any other odd number of moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    moves: Vec<String>,
+    // beats[a][b] is true if move a beats move b
+    beats: Vec<Vec<bool>>,
 }
 
-impl From<&str> for Hand {
-    fn from(input: &str) -> Self {
-        match input {
-            "A" | "X" => Rock,
-            "B" | "Y" => Paper,
-            "C" | "Z" => Scissors,
-            _ => panic!("Could not match"),
-        }
+impl Game {
+    // Every move beats the moves an odd number of steps before it in the cycle,
+    // e.g. Paper beats Rock and Rock beats Scissors
+    pub fn cyclic<I, S>(moves: I) -> Result<Self>
+        where I: IntoIterator<Item=S>, S: Into<String> {
+        let moves = moves.into_iter().map(S::into).collect::<Vec<String>>();
+        let n = moves.len();
+        let beats = (0..n)
+            .map(|a| (0..n).map(|b| (a + n - b) % n % 2 == 1).collect())
+            .collect();
+        Self::new(moves, beats)
     }
-}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
-        todo!()
+    // `rules` lists every (winner, loser) pair; each pair of moves has to be decided exactly once
+    pub fn with_rules<I, S>(moves: I, rules: &[(&str, &str)]) -> Result<Self>
+        where I: IntoIterator<Item=S>, S: Into<String> {
+        let moves = moves.into_iter().map(S::into).collect::<Vec<String>>();
+        let position = |name: &str| moves.iter()
+            .position(|m| m == name)
+            .ok_or_else(|| Error::parse(format!("Unknown move '{name}' in the rules")));
+        let mut beats = vec![vec![false; moves.len()]; moves.len()];
+        for (winner, loser) in rules {
+            let (a, b) = (position(winner)?, position(loser)?);
+            if a == b {
+                return Err(Error::parse(format!("'{winner}' cannot beat itself")));
+            }
+            if beats[a][b] || beats[b][a] {
+                return Err(Error::parse(format!("'{winner}' against '{loser}' is decided twice")));
+            }
+            beats[a][b] = true;
+        }
+        for a in 0..moves.len() {
+            for b in a + 1..moves.len() {
+                if !beats[a][b] && !beats[b][a] {
+                    return Err(Error::parse(format!("Nothing decides '{}' against '{}'", moves[a], moves[b])));
+                }
+            }
+        }
+        Self::new(moves, beats)
     }
 
-    fn lt(&self, other: &Self) -> bool {
-        match self {
-            Rock => *other == Paper,
-            Paper => *other == Scissors,
-            Scissors => *other == Rock,
+    fn new(moves: Vec<String>, beats: Vec<Vec<bool>>) -> Result<Self> {
+        let n = moves.len();
+        // with a single move nothing can be won or lost
+        if n < 3 || n.is_multiple_of(2) {
+            return Err(Error::parse(format!("A cyclic game needs an odd number of at least 3 moves, found {n}")));
+        }
+        for (i, name) in moves.iter().enumerate() {
+            if moves[..i].contains(name) {
+                return Err(Error::parse(format!("Duplicate move '{name}'")));
+            }
+            // otherwise some outcome could not be reached against some move
+            let wins = beats[i].iter().filter(|beats| **beats).count();
+            if wins != n / 2 {
+                return Err(Error::parse(format!("'{name}' beats {wins} moves, expected {}", n / 2)));
+            }
         }
+        Ok(Self {
+            moves,
+            beats,
+        })
     }
 
-    fn le(&self, other: &Self) -> bool {
-        self.lt(other) || self == other
+    pub fn rock_paper_scissors() -> Self {
+        Self::cyclic(["Rock", "Paper", "Scissors"]).expect("Three moves form a cyclic game")
     }
 
-    fn gt(&self, other: &Self) -> bool {
-        match self {
-            Rock => *other == Scissors,
-            Paper => *other == Rock,
-            Scissors => *other == Paper,
-        }
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::cyclic(["Rock", "Paper", "Scissors", "Spock", "Lizard"]).expect("Five moves form a cyclic game")
     }
 
-    fn ge(&self, other: &Self) -> bool {
-        self.gt(other) || self == other
+    pub fn moves(&self) -> &[String] {
+        &self.moves
     }
-}
 
-impl Hand {
-    fn against(self, other: Hand) -> Outcome {
-        if self > other {
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.moves.iter().position(|m| m == name)
+    }
+
+    pub fn beats(&self, a: usize, b: usize) -> bool {
+        self.beats[a][b]
+    }
+
+    // The outcome of playing move `a` against move `b`
+    pub fn outcome(&self, a: usize, b: usize) -> Outcome {
+        if self.beats[a][b] {
             Win
-        } else if self == other {
+        } else if a == b {
             Draw
         } else {
             Lose
         }
     }
 
-    fn value(&self) -> u32 {
+    // The first move, in the order of the game, that reaches `outcome` against `other`
+    pub fn respond(&self, other: usize, outcome: Outcome) -> usize {
+        (0..self.moves.len())
+            .find(|a| self.outcome(*a, other) == outcome)
+            .expect("Every outcome can be reached in a cyclic game")
+    }
+}
+
+fn rock_paper_scissors() -> &'static Game {
+    static GAME: OnceLock<Game> = OnceLock::new();
+    GAME.get_or_init(Game::rock_paper_scissors)
+}
+
+// Ordered like the moves of Game::rock_paper_scissors; who wins is up to the game, not the order
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
+pub enum Hand {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl FromStr for Hand {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        match input {
+            "A" | "X" => Ok(Rock),
+            "B" | "Y" => Ok(Paper),
+            "C" | "Z" => Ok(Scissors),
+            _ => Err(Error::parse(format!("Unknown hand '{input}'"))),
+        }
+    }
+}
+
+impl Hand {
+    pub const ALL: [Hand; 3] = [Rock, Paper, Scissors];
+
+    pub fn against(self, other: Hand) -> Outcome {
+        rock_paper_scissors().outcome(self as usize, other as usize)
+    }
+
+    pub fn value(&self) -> u32 {
         match self {
             Rock => 1,
             Paper => 2,
@@ -71,86 +163,604 @@ impl Hand {
     }
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum Outcome {
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Outcome {
     Win,
     Lose,
     Draw,
 }
 
 impl Outcome {
-    fn value(&self) -> u32 {
+    pub const ALL: [Outcome; 3] = [Win, Lose, Draw];
+
+    // Names used by rules files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Win => "win",
+            Lose => "lose",
+            Draw => "draw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|outcome| outcome.name() == name)
+    }
+
+    pub fn value(&self) -> u32 {
         match self {
             Win => 6,
             Lose => 0,
             Draw => 3,
         }
     }
-    fn against_hand(self, other: Hand) -> Hand {
-        vec![Rock, Paper, Scissors].into_iter()
-            .find(|hand| hand.against(other) == self)
-            .unwrap()
+    pub fn against_hand(self, other: Hand) -> Hand {
+        Hand::ALL[rock_paper_scissors().respond(other as usize, self)]
     }
 }
 
-impl From<&str> for Outcome {
-    fn from(input: &str) -> Self {
+impl FromStr for Outcome {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
         match input {
-            "X" => Lose,
-            "Y" => Draw,
-            "Z" => Win,
-            _ => panic!("Could not match"),
+            "X" => Ok(Lose),
+            "Y" => Ok(Draw),
+            "Z" => Ok(Win),
+            _ => Err(Error::parse(format!("Unknown outcome '{input}'"))),
         }
     }
 }
 
-fn calculate1(lines: Vec<String>) -> u32 {
-    let mut score = 0u32;
-    for line in lines {
-        let parts = line.split(" ").collect::<Vec<&str>>();
-        if parts.len() != 2 {
-            panic!("Cannot parse line");
+/// How a strategy guide is decoded and scored: the game, what the symbols of both columns mean
+/// and the points for every move and outcome. Built with [`Rules::builder`] or read from a file:
+///
+/// ```toml
+/// moves = ["Rock", "Paper", "Scissors", "Spock", "Lizard"]
+/// # optional, defaults to the cyclic order of the moves
+/// beats = [["Paper", "Rock"], ["Rock", "Scissors"], ...]
+///
+/// [opponent]
+/// A = "Rock"
+/// [player]  # second column in part 1
+/// X = "Rock"
+/// [outcome] # second column in part 2
+/// X = "lose"
+/// [points]
+/// Rock = 1
+/// win = 6
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    game: Game,
+    opponent: HashMap<String, usize>,
+    player: HashMap<String, usize>,
+    outcomes: HashMap<String, Outcome>,
+    move_points: Vec<u32>,
+    outcome_points: HashMap<Outcome, u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RulesBuilder {
+    game: Option<Game>,
+    opponent: Vec<(String, String)>,
+    player: Vec<(String, String)>,
+    outcomes: Vec<(String, Outcome)>,
+    move_points: Vec<(String, u32)>,
+    outcome_points: Vec<(Outcome, u32)>,
+}
+
+impl RulesBuilder {
+    // Defaults to Rock-Paper-Scissors
+    pub fn game(mut self, game: Game) -> Self {
+        self.game = Some(game);
+        self
+    }
+
+    pub fn opponent<S, M>(mut self, symbol: S, name: M) -> Self
+        where S: Into<String>, M: Into<String> {
+        self.opponent.push((symbol.into(), name.into()));
+        self
+    }
+
+    pub fn player<S, M>(mut self, symbol: S, name: M) -> Self
+        where S: Into<String>, M: Into<String> {
+        self.player.push((symbol.into(), name.into()));
+        self
+    }
+
+    pub fn outcome<S>(mut self, symbol: S, outcome: Outcome) -> Self
+        where S: Into<String> {
+        self.outcomes.push((symbol.into(), outcome));
+        self
+    }
+
+    pub fn move_points<M>(mut self, name: M, points: u32) -> Self
+        where M: Into<String> {
+        self.move_points.push((name.into(), points));
+        self
+    }
+
+    pub fn outcome_points(mut self, outcome: Outcome, points: u32) -> Self {
+        self.outcome_points.push((outcome, points));
+        self
+    }
+
+    pub fn build(self) -> Result<Rules> {
+        let game = self.game.unwrap_or_else(Game::rock_paper_scissors);
+        let position = |name: &str| game.position(name)
+            .ok_or_else(|| Error::parse(format!("Unknown move '{name}'")));
+        let symbols = |column: &str, pairs: Vec<(String, String)>| {
+            let mut symbols = HashMap::new();
+            for (symbol, name) in pairs {
+                check_symbol(&symbol, column, &symbols)?;
+                symbols.insert(symbol, position(&name)?);
+            }
+            Ok::<_, Error>(symbols)
+        };
+        let opponent = symbols("opponent", self.opponent)?;
+        let player = symbols("player", self.player)?;
+        let mut outcomes = HashMap::new();
+        for (symbol, outcome) in self.outcomes {
+            check_symbol(&symbol, "outcome", &outcomes)?;
+            outcomes.insert(symbol, outcome);
         }
-        let other: Hand = parts[0].into();
-        let hand: Hand = parts[1].into();
-        let outcome = hand.against(other);
-        score += hand.value() + outcome.value();
+        let mut move_points = vec![None; game.moves().len()];
+        for (name, points) in self.move_points {
+            move_points[position(&name)?] = Some(points);
+        }
+        let move_points = move_points.into_iter()
+            .zip(game.moves())
+            .map(|(points, name)| points.ok_or_else(|| Error::parse(format!("Missing points for move '{name}'"))))
+            .collect::<Result<Vec<u32>>>()?;
+        let outcome_points = self.outcome_points.into_iter().collect::<HashMap<Outcome, u32>>();
+        if let Some(outcome) = Outcome::ALL.iter().find(|outcome| !outcome_points.contains_key(outcome)) {
+            return Err(Error::parse(format!("Missing points for outcome '{}'", outcome.name())));
+        }
+        Ok(Rules {
+            game,
+            opponent,
+            player,
+            outcomes,
+            move_points,
+            outcome_points,
+        })
+    }
+}
+
+// Symbols are separated by a space in the guide, so they cannot contain one
+fn check_symbol<T>(symbol: &str, column: &str, known: &HashMap<String, T>) -> Result<()> {
+    if symbol.is_empty() || symbol.contains(char::is_whitespace) {
+        return Err(Error::parse(format!("Invalid {column} symbol '{symbol}'")));
+    }
+    if known.contains_key(symbol) {
+        return Err(Error::parse(format!("The {column} symbol '{symbol}' is mapped twice")));
+    }
+    Ok(())
+}
+
+fn string(entry: &Entry) -> Result<&str> {
+    match &entry.value {
+        Value::String(s) => Ok(s),
+        _ => Err(Error::parse(format!("Expected a string for '{}'", entry.key)).at(entry.line, entry.column)),
+    }
+}
+
+// Splits a line into its two columns and decodes both, positioning any error at the offending column.
+fn parse_round<A, B, F, G>(line: &str, i: usize, first: F, second: G) -> Result<(A, B)>
+    where F: FnOnce(&str) -> Result<A>, G: FnOnce(&str) -> Result<B> {
+    let parts = line.split(' ').collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(Error::parse(format!("Expected two columns, found {}", parts.len())).at(i + 1, 1));
     }
-    score
+    let first = first(parts[0]).map_err(|e| e.at(i + 1, 1))?;
+    let second = second(parts[1]).map_err(|e| e.at(i + 1, parts[0].len() + 2))?;
+    Ok((first, second))
 }
 
-fn calculate2(lines: Vec<String>) -> u32 {
-    let mut score = 0u32;
-    for line in lines {
-        let parts = line.split(" ").collect::<Vec<&str>>();
-        if parts.len() != 2 {
-            panic!("Cannot parse line");
+fn decode<T>(symbols: &HashMap<String, T>, symbol: &str, what: &str) -> Result<T>
+    where T: Copy {
+    symbols.get(symbol)
+        .copied()
+        .ok_or_else(|| Error::parse(format!("Unknown {what} '{symbol}'")))
+}
+
+fn add(score: u32, round: u32, line: usize) -> Result<u32> {
+    score.checked_add(round)
+        .ok_or_else(|| Error::solve("Score does not fit into u32").at_line(line))
+}
+
+/// One round of the guide, its second column decoded both as the move to play and as the
+/// outcome to reach. A symbol that only one of the readings knows fails just the part that
+/// uses the other one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round {
+    /// One based line of the round in the guide.
+    pub line: usize,
+    pub opponent: usize,
+    played: Option<usize>,
+    outcome: Option<Outcome>,
+    // the second column and where it starts, to position the error of a reading that lacks it
+    symbol: String,
+    column: usize,
+}
+
+impl Round {
+    pub fn played(&self) -> Result<usize> {
+        self.played
+            .ok_or_else(|| Error::parse(format!("Unknown hand '{}'", self.symbol)).at(self.line, self.column))
+    }
+
+    pub fn outcome(&self) -> Result<Outcome> {
+        self.outcome
+            .ok_or_else(|| Error::parse(format!("Unknown outcome '{}'", self.symbol)).at(self.line, self.column))
+    }
+}
+
+impl Rules {
+    pub fn builder() -> RulesBuilder {
+        RulesBuilder::default()
+    }
+
+    pub fn load<P>(path: P) -> Result<Self>
+        where P: AsRef<Path> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| Error::io(e).in_file(path))?;
+        Self::parse(&text).map_err(|e| e.in_file(path))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let tables = toml::parse(text)?;
+        let mut moves = None;
+        let mut beats = None;
+        for entry in &tables[0].entries {
+            let value = match &entry.value {
+                Value::Array(items) => items,
+                _ => return Err(Error::parse(format!("Expected a list for '{}'", entry.key)).at(entry.line, entry.column)),
+            };
+            let names = |items: &[Value]| items.iter()
+                .map(|item| match item {
+                    Value::String(name) => Ok(name.clone()),
+                    _ => Err(Error::parse("Expected the name of a move").at(entry.line, entry.column)),
+                })
+                .collect::<Result<Vec<String>>>();
+            match entry.key.as_str() {
+                "moves" => moves = Some(names(value)?),
+                "beats" => beats = Some(value.iter()
+                    .map(|pair| match pair {
+                        Value::Array(pair) => match names(pair)?.as_slice() {
+                            [winner, loser] => Ok((winner.clone(), loser.clone())),
+                            _ => Err(Error::parse("Expected [winner, loser]").at(entry.line, entry.column)),
+                        },
+                        _ => Err(Error::parse("Expected [winner, loser]").at(entry.line, entry.column)),
+                    })
+                    .collect::<Result<Vec<(String, String)>>>()?),
+                key => return Err(Error::parse(format!("Unknown key '{key}', expected moves or beats")).at(entry.line, entry.column)),
+            }
         }
-        let other: Hand = parts[0].into();
-        let outcome: Outcome = parts[1].into();
-        let hand = outcome.against_hand(other);
-        score += hand.value() + outcome.value();
+        let line = tables[0].entries.first().map_or(1, |entry| entry.line);
+        let game = match (moves, beats) {
+            (Some(moves), Some(beats)) => {
+                let rules = beats.iter()
+                    .map(|(winner, loser)| (winner.as_str(), loser.as_str()))
+                    .collect::<Vec<(&str, &str)>>();
+                Game::with_rules(moves, &rules)
+            }
+            (Some(moves), None) => Game::cyclic(moves),
+            (None, None) => Ok(Game::rock_paper_scissors()),
+            (None, Some(_)) => Err(Error::parse("'beats' needs the list of 'moves'")),
+        }.map_err(|e| e.at_line(line))?;
+
+        let mut builder = Rules::builder().game(game.clone());
+        for table in &tables[1..] {
+            let name = table.name.as_deref().unwrap_or_default();
+            for entry in &table.entries {
+                let at = |e: Error| e.at(entry.line, entry.column);
+                let known_move = |name: &str| game.position(name)
+                    .map(|_| name.to_string())
+                    .ok_or_else(|| at(Error::parse(format!("Unknown move '{name}'"))));
+                builder = match name {
+                    "opponent" => builder.opponent(&entry.key, known_move(string(entry)?)?),
+                    "player" => builder.player(&entry.key, known_move(string(entry)?)?),
+                    "outcome" => {
+                        let outcome = string(entry)?;
+                        let outcome = Outcome::from_name(outcome)
+                            .ok_or_else(|| at(Error::parse(format!("Unknown outcome '{outcome}', expected win, draw or lose"))))?;
+                        builder.outcome(&entry.key, outcome)
+                    }
+                    "points" => {
+                        let points = match entry.value {
+                            Value::Integer(points) => u32::try_from(points)
+                                .map_err(|_| at(Error::parse(format!("Too many points for '{}'", entry.key))))?,
+                            _ => return Err(at(Error::parse(format!("Expected a number of points for '{}'", entry.key)))),
+                        };
+                        match Outcome::from_name(&entry.key) {
+                            Some(outcome) => builder.outcome_points(outcome, points),
+                            None => builder.move_points(known_move(&entry.key)?, points),
+                        }
+                    }
+                    _ => return Err(Error::parse(format!("Unknown section [{name}], expected opponent, player, outcome or points"))
+                        .at_line(table.line)),
+                };
+            }
+        }
+        builder.build()
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn move_points(&self, played: usize) -> u32 {
+        self.move_points[played]
+    }
+
+    pub fn outcome_points(&self, outcome: Outcome) -> u32 {
+        self.outcome_points[&outcome]
+    }
+
+    // The score of one round, which may not fit into u32 with rules of many points either
+    fn points(&self, played: usize, outcome: Outcome) -> Result<u32> {
+        self.move_points(played).checked_add(self.outcome_points(outcome))
+            .ok_or_else(|| Error::solve("Score does not fit into u32"))
+    }
+
+    // Decodes one line of the guide; `i` is its zero based index
+    pub fn read_round(&self, line: &str, i: usize) -> Result<Round> {
+        let second = |s: &str| match (self.player.get(s), self.outcomes.get(s)) {
+            (None, None) => Err(Error::parse(format!("Unknown hand or outcome '{s}'"))),
+            (played, outcome) => Ok((played.copied(), outcome.copied())),
+        };
+        let (opponent, (played, outcome)) = parse_round(line, i, |s| decode(&self.opponent, s, "hand"), second)?;
+        let (first, symbol) = line.split_once(' ').expect("The round has two columns");
+        Ok(Round {
+            line: i + 1,
+            opponent,
+            played,
+            outcome,
+            symbol: symbol.to_string(),
+            column: first.len() + 2,
+        })
+    }
+
+    pub fn read_guide(&self, lines: &[String]) -> Result<Vec<Round>> {
+        lines.iter()
+            .enumerate()
+            .map(|(i, line)| self.read_round(line, i))
+            .collect()
+    }
+
+    // The second column is the move to play
+    fn score1(&self, round: &Round) -> Result<u32> {
+        let played = round.played()?;
+        self.points(played, self.game.outcome(played, round.opponent))
+            .map_err(|e| e.at_line(round.line))
+    }
+
+    // The second column is the outcome to reach
+    fn score2(&self, round: &Round) -> Result<u32> {
+        let outcome = round.outcome()?;
+        self.points(self.game.respond(round.opponent, outcome), outcome)
+            .map_err(|e| e.at_line(round.line))
+    }
+
+    pub fn total1(&self, rounds: &[Round]) -> Result<u32> {
+        rounds.iter().try_fold(0, |score, round| add(score, self.score1(round)?, round.line))
+    }
+
+    pub fn total2(&self, rounds: &[Round]) -> Result<u32> {
+        rounds.iter().try_fold(0, |score, round| add(score, self.score2(round)?, round.line))
+    }
+
+    pub fn calculate1(&self, lines: &[String]) -> Result<u32> {
+        self.total1(&self.read_guide(lines)?)
+    }
+
+    pub fn calculate2(&self, lines: &[String]) -> Result<u32> {
+        self.total2(&self.read_guide(lines)?)
+    }
+
+    // Scores both interpretations of the strategy guide in one pass, without keeping any round
+    pub fn score_rounds(&self, lines: &mut dyn LineSource) -> Result<(u32, u32)> {
+        let mut scores = (0, 0);
+        lines::try_for_each(lines, |number, line| {
+            let round = self.read_round(line, number - 1)?;
+            scores.0 = add(scores.0, self.score1(&round)?, number)?;
+            scores.1 = add(scores.1, self.score2(&round)?, number)?;
+            Ok(())
+        })?;
+        Ok(scores)
     }
-    score
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day2.txt");
-    if let Ok(lines) = lines {
-        let score = calculate1(lines.clone());
-        println!("Day 2: First Score = {score}");
-        let score = calculate2(lines);
-        println!("Day 2: Second Score = {score}");
+// The puzzle's rules: A/B/C and X/Y/Z for the hands, X/Y/Z for lose/draw/win
+impl Default for Rules {
+    fn default() -> Self {
+        let game = rock_paper_scissors();
+        let mut builder = Rules::builder().game(game.clone());
+        for (hand, (opponent, player)) in Hand::ALL.iter().zip([("A", "X"), ("B", "Y"), ("C", "Z")]) {
+            let name = &game.moves()[*hand as usize];
+            builder = builder.opponent(opponent, name)
+                .player(player, name)
+                .move_points(name, hand.value());
+        }
+        for (outcome, symbol) in [(Lose, "X"), (Draw, "Y"), (Win, "Z")] {
+            builder = builder.outcome(symbol, outcome)
+                .outcome_points(outcome, outcome.value());
+        }
+        builder.build().expect("The puzzle's rules are complete")
+    }
+}
+
+/// A move played in one round and what it earned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Play {
+    pub played: usize,
+    pub outcome: Outcome,
+    pub score: u32,
+}
+
+/// One round of the guide under both readings of its second column, next to the best move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundComparison {
+    /// One based line of the round in the guide.
+    pub line: usize,
+    pub opponent: usize,
+    /// The second column read as the move to play.
+    pub first: Play,
+    /// The second column read as the outcome to reach.
+    pub second: Play,
+    pub best: Play,
+}
+
+impl Rules {
+    pub fn play(&self, played: usize, other: usize) -> Result<Play> {
+        let outcome = self.game.outcome(played, other);
+        Ok(Play {
+            played,
+            outcome,
+            score: self.points(played, outcome)?,
+        })
+    }
+
+    // The highest scoring move against `other`, the first in the game's order on ties
+    pub fn best_play(&self, other: usize) -> Result<Play> {
+        let mut best: Option<Play> = None;
+        for played in 0..self.game.moves().len() {
+            let play = self.play(played, other)?;
+            if best.is_none_or(|best| play.score > best.score) {
+                best = Some(play);
+            }
+        }
+        Ok(best.expect("A game has at least one move"))
+    }
+
+    fn compare_round(&self, round: &Round) -> Result<RoundComparison> {
+        Ok(RoundComparison {
+            line: round.line,
+            opponent: round.opponent,
+            first: self.play(round.played()?, round.opponent)?,
+            second: self.play(self.game.respond(round.opponent, round.outcome()?), round.opponent)?,
+            best: self.best_play(round.opponent)?,
+        })
+    }
+
+    pub fn compare(&self, lines: &[String]) -> Result<Vec<RoundComparison>> {
+        self.read_guide(lines)?
+            .iter()
+            .map(|round| self.compare_round(round).map_err(|e| e.at_line(round.line)))
+            .collect()
+    }
+
+    // How often the opponent of the guide plays each move, as probabilities
+    pub fn opponent_distribution(&self, lines: &[String]) -> Result<Vec<f64>> {
+        let mut counts = vec![0usize; self.game.moves().len()];
+        for (i, line) in lines.iter().enumerate() {
+            let (opponent, _) = parse_round(line, i, |s| decode(&self.opponent, s, "hand"), |_| Ok(()))?;
+            counts[opponent] += 1;
+        }
+        normalize(&counts.iter().map(|count| *count as f64).collect::<Vec<f64>>())
+    }
+
+    // Mean score per round of always playing `played` against an opponent that picks its
+    // moves with the given weights, one per move of the game
+    pub fn expected_score(&self, played: usize, weights: &[f64]) -> Result<f64> {
+        normalize(weights)?.iter()
+            .enumerate()
+            .map(|(other, p)| Ok(p * f64::from(self.play(played, other)?.score)))
+            .sum()
+    }
+
+    // The single move with the highest expected score against the weights
+    pub fn best_response(&self, weights: &[f64]) -> Result<(usize, f64)> {
+        let mut best = (0, f64::MIN);
+        for played in 0..self.game.moves().len() {
+            let expected = self.expected_score(played, weights)?;
+            if expected > best.1 {
+                best = (played, expected);
+            }
+        }
+        Ok(best)
+    }
+}
+
+fn normalize(weights: &[f64]) -> Result<Vec<f64>> {
+    if weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) {
+        return Err(Error::solve("Weights of moves must be finite and not negative"));
+    }
+    let total = weights.iter().sum::<f64>();
+    if total == 0.0 {
+        return Err(Error::solve("At least one move needs a positive weight"));
+    }
+    Ok(weights.iter().map(|weight| weight / total).collect())
+}
+
+fn standard_rules() -> &'static Rules {
+    static RULES: OnceLock<Rules> = OnceLock::new();
+    RULES.get_or_init(Rules::default)
+}
+
+pub fn calculate1(lines: &[String]) -> Result<u32> {
+    standard_rules().calculate1(lines)
+}
+
+pub fn calculate2(lines: &[String]) -> Result<u32> {
+    standard_rules().calculate2(lines)
+}
+
+pub fn score_rounds(lines: &mut dyn LineSource) -> Result<(u32, u32)> {
+    standard_rules().score_rounds(lines)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Day2 {
+    rules: Rules,
+}
+
+impl Day2 {
+    // Scores variant tournaments
+    pub fn with_rules(rules: Rules) -> Self {
+        Self {
+            rules,
+        }
+    }
+}
+
+impl Solution for Day2 {
+    type Input = Vec<Round>;
+
+    const PART1: &'static str = "First Score";
+    const PART2: &'static str = "Second Score";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        let mut rounds = vec![];
+        lines::try_for_each(lines, |number, line| {
+            rounds.push(self.rules.read_round(line, number - 1)?);
+            Ok(())
+        })?;
+        Ok(rounds)
+    }
+
+    fn part1(&self, rounds: &Self::Input) -> Result<Answer> {
+        self.rules.total1(rounds).map(Answer::from)
+    }
+
+    fn part2(&self, rounds: &Self::Input) -> Result<Answer> {
+        self.rules.total2(rounds).map(Answer::from)
+    }
+
+    fn stream(&self, lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>> {
+        Some(self.rules.score_rounds(lines).map(|(first, second)| (first.into(), second.into())))
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculate1, calculate2};
+    use crate::lines::SliceLines;
+    use crate::solution::Solution;
+    use super::{calculate1, calculate2, score_rounds, Day2, Game, Hand, Outcome, Play, Rules};
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "A Y",
             "B X",
             "C Z",
@@ -160,14 +770,149 @@ mod tests {
     #[test]
     pub fn test1() {
         let lines = test_data();
-        let score = calculate1(lines);
+        let score = calculate1(&lines).unwrap();
         assert_eq!(score, 15);
     }
 
     #[test]
     pub fn test2() {
         let lines = test_data();
-        let score = calculate2(lines);
+        let score = calculate2(&lines).unwrap();
         assert_eq!(score, 12);
+        assert_eq!(score_rounds(&mut SliceLines::new(&lines)).unwrap(), (15, 12));
+    }
+
+    #[test]
+    pub fn test_errors() {
+        let error = calculate1(&["A Y".to_string(), "B Q".to_string()]).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(3)));
+        let error = calculate2(&["AY".to_string()]).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(1)));
+
+        // a malformed guide fails while parsing, before any part is solved
+        let error = Day2::default().parse(&mut SliceLines::new(&["A Y".to_string(), "D X".to_string()])).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(1)));
+        let rounds = Day2::default().parse(&mut SliceLines::new(&test_data())).unwrap();
+        assert_eq!(rounds[1].played().unwrap(), 0);
+        assert_eq!(rounds[2].outcome().unwrap(), Outcome::Win);
+    }
+
+    #[test]
+    pub fn test_hand_order() {
+        let mut hands = vec![Hand::Scissors, Hand::Rock, Hand::Paper];
+        hands.sort();
+        assert_eq!(hands, Hand::ALL);
+        assert_eq!(hands.iter().max(), Some(&Hand::Scissors));
+        assert_eq!(Hand::Rock.against(Hand::Scissors), Outcome::Win);
+        assert_eq!(Outcome::Lose.against_hand(Hand::Rock), Hand::Scissors);
+    }
+
+    #[test]
+    pub fn test_game() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let rules = [
+            ("Scissors", "Paper"), ("Paper", "Rock"), ("Rock", "Lizard"), ("Lizard", "Spock"), ("Spock", "Scissors"),
+            ("Scissors", "Lizard"), ("Lizard", "Paper"), ("Paper", "Spock"), ("Spock", "Rock"), ("Rock", "Scissors"),
+        ];
+        assert_eq!(Game::with_rules(game.moves().to_vec(), &rules).unwrap(), game);
+        let (lizard, spock) = (game.position("Lizard").unwrap(), game.position("Spock").unwrap());
+        assert_eq!(game.outcome(lizard, spock), Outcome::Win);
+        assert_eq!(game.outcome(spock, lizard), Outcome::Lose);
+        assert_eq!(game.outcome(spock, spock), Outcome::Draw);
+        assert!(game.beats(game.respond(spock, Outcome::Win), spock));
+
+        assert!(Game::cyclic(["Rock", "Paper"]).is_err());
+        assert!(Game::cyclic(["Rock"]).is_err());
+        assert!(Game::cyclic(Vec::<String>::new()).is_err());
+        assert!(Rules::parse("moves = [\"Rock\"]").is_err());
+        assert!(Game::with_rules(["Rock", "Paper", "Scissors"], &rules[..1]).is_err());
+        assert!(Game::with_rules(["A", "B", "C"], &[("A", "B"), ("A", "C"), ("B", "C")]).is_err());
+        assert!(Game::with_rules(["A", "B", "C"], &[("A", "B"), ("B", "A"), ("C", "A")]).is_err());
+    }
+
+    #[test]
+    pub fn test_tournament() {
+        let rules = Rules::default();
+        let lines = test_data();
+        let rounds = rules.compare(&lines).unwrap();
+        assert_eq!(rounds.len(), 3);
+        assert_eq!(rounds[0].first, Play { played: 1, outcome: Outcome::Win, score: 8 });
+        assert_eq!(rounds[0].second, Play { played: 0, outcome: Outcome::Draw, score: 4 });
+        assert_eq!(rounds[2].best, Play { played: 0, outcome: Outcome::Win, score: 7 });
+        assert_eq!(rounds.iter().map(|round| round.first.score).sum::<u32>(), 15);
+        assert_eq!(rounds.iter().map(|round| round.second.score).sum::<u32>(), 12);
+
+        let uniform = rules.opponent_distribution(&lines).unwrap();
+        assert_eq!(uniform, vec![1.0 / 3.0; 3]);
+        assert!((rules.expected_score(0, &uniform).unwrap() - 4.0).abs() < 1e-9);
+        // paper beats the rock of an opponent that never plays anything else
+        assert_eq!(rules.best_response(&[1.0, 0.0, 0.0]).unwrap(), (1, 8.0));
+        assert!(rules.expected_score(0, &[0.0, 0.0, 0.0]).is_err());
+
+        // scores that overflow fail the tournament just like the parts
+        let mut builder = Rules::builder().game(Game::cyclic(["Rock", "Paper", "Scissors"]).unwrap());
+        for ((opponent, player), (name, points)) in [("A", "X"), ("B", "Y"), ("C", "Z")].into_iter().zip([("Rock", u32::MAX), ("Paper", 2), ("Scissors", 3)]) {
+            builder = builder.opponent(opponent, name).player(player, name).move_points(name, points);
+        }
+        for (outcome, symbol) in [(Outcome::Lose, "X"), (Outcome::Draw, "Y"), (Outcome::Win, "Z")] {
+            builder = builder.outcome(symbol, outcome).outcome_points(outcome, outcome.value());
+        }
+        let rules = builder.build().unwrap();
+        assert!(rules.play(0, 0).is_err());
+        assert_eq!(rules.compare(&lines).unwrap_err().line(), Some(1));
+        assert_eq!(rules.calculate2(&lines).unwrap_err().line(), Some(1));
+    }
+
+    #[test]
+    pub fn test_rules() {
+        let text = "\
+[opponent]
+A = \"Rock\"
+B = \"Paper\"
+C = \"Scissors\"
+[player]
+X = \"Rock\"
+Y = \"Paper\"
+Z = \"Scissors\"
+[outcome]
+X = \"lose\"
+Y = \"draw\"
+Z = \"win\"
+[points]
+Rock = 1
+Paper = 2
+Scissors = 3
+win = 6
+draw = 3
+lose = 0
+";
+        assert_eq!(Rules::parse(text).unwrap(), Rules::default());
+
+        // Rock-Paper-Scissors-Lizard-Spock, where only winning counts
+        let mut builder = Rules::builder().game(Game::rock_paper_scissors_lizard_spock());
+        for (symbol, name) in ["R", "P", "S", "K", "L"].iter().zip(["Rock", "Paper", "Scissors", "Spock", "Lizard"]) {
+            builder = builder.opponent(*symbol, name).player(*symbol, name).move_points(name, 0);
+        }
+        let rules = builder.outcome("W", Outcome::Win)
+            .outcome("D", Outcome::Draw)
+            .outcome_points(Outcome::Win, 1)
+            .outcome_points(Outcome::Draw, 0)
+            .outcome_points(Outcome::Lose, 0)
+            .build()
+            .unwrap();
+        let lines = ["L K", "K L", "L S", "R W"].map(str::to_string);
+        assert_eq!(rules.calculate1(&lines[..3]).unwrap(), 2);
+        let error = rules.calculate1(&lines).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(4), Some(3)));
+        assert_eq!(rules.calculate2(&lines[3..]).unwrap(), 1);
+        // only the reading that does not know the symbol fails
+        let rounds = rules.read_guide(&lines[3..]).unwrap();
+        assert_eq!(rules.total2(&rounds).unwrap(), 1);
+        assert_eq!(rules.total1(&rounds).unwrap_err().reason(), "Unknown hand 'W'");
+        assert!(rules.read_guide(&["R Q".to_string()]).is_err());
+
+        let error = Rules::parse(&text.replace("\"Paper\"\nC", "\"Papier\"\nC")).unwrap_err();
+        assert_eq!(error.line(), Some(3));
+        assert!(Rules::parse(&text.replace("lose = 0\n", "")).is_err());
     }
 }
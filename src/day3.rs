@@ -1,75 +1,353 @@
-use std::collections::HashSet;
-use crate::lines;
-
-fn priority(c: char) -> u32 {
-    if c.is_uppercase() {
-        c as u32 - 'A' as u32 + 27
-    } else if c.is_lowercase() {
-        c as u32 - 'a' as u32 + 1
-    } else {
-        panic!("invalid char")
-    }
-}
-
-fn find_doubles_in_elves(group: &[String]) -> HashSet<char> {
-    let s1 = &group[0];
-    let s2 = &group[1];
-    let s3 = &group[2];
-    let c1 = find_doubles(s1, s2);
-    let c2 = find_doubles(s2, s3);
-    let c3 = find_doubles(s3, s1);
-    let i1: HashSet<char> = c1.intersection(&c2).map(|c| *c).collect();
-    let i2: HashSet<char> = i1.intersection(&c3).map(|c| *c).collect();
-    assert_eq!(i2.len(), 1);
-    i2
-}
-
-fn find_doubles_in_backpack(s: &String) -> HashSet<char> {
-    let len = s.len();
-    let s1 = &s[..len / 2];
-    let s2 = &s[len / 2..];
-    find_doubles(s1, s2)
-}
-
-fn find_doubles(s1: &str, s2: &str) -> HashSet<char> {
-    s1.chars()
-        .filter(|c| s2.chars().into_iter()
-            .any(|c2| c2 == *c)
-        )
-        .collect()
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::lines::{self, LineSource};
+use crate::solution::{Answer, Solution};
+
+// Only the ASCII letters are items
+pub fn priority(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
+}
+
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => char::from(b'a' + (priority - 1) as u8),
+        _ => char::from(b'A' + (priority - 27) as u8),
+    }
+}
+
+/// Everything that can be wrong with the rucksacks of day 3. The [`Error`] wrapping it is
+/// positioned at the offending line, or at the first line of the offending group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RucksackError {
+    InvalidItem { item: char, column: usize },
+    OddLength { length: usize },
+    IncompleteGroup { size: usize, found: usize },
+    NoBadge { size: usize },
+    MultipleBadges { badges: String },
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem { item, .. } => write!(f, "Invalid item '{item}'"),
+            RucksackError::OddLength { length } => write!(f, "Rucksack of {length} items cannot be split into two compartments"),
+            RucksackError::IncompleteGroup { size, found } => write!(f, "Expected a group of {size} elves, found only {found}"),
+            RucksackError::NoBadge { size } => write!(f, "The group of {size} elves starting here has no item in common"),
+            RucksackError::MultipleBadges { badges } => write!(f, "The group starting here has more than one item in common: {badges}"),
+        }
+    }
+}
+
+impl std::error::Error for RucksackError {}
+
+impl RucksackError {
+    fn at_line(self, line: usize) -> Error {
+        match self {
+            RucksackError::InvalidItem { column, .. } => Error::typed(ErrorKind::Parse, self).at(line, column),
+            RucksackError::OddLength { .. } => Error::typed(ErrorKind::Parse, self).at_line(line),
+            _ => Error::typed(ErrorKind::Solve, self).at_line(line),
+        }
+    }
+}
+
+/// The distinct items of a rucksack or compartment, bit `n` standing for the item with
+/// priority `n`, so intersecting any number of rucksacks is one AND per rucksack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Items(u64);
+
+impl Items {
+    // every item from 'a' (priority 1) to 'Z' (priority 52)
+    pub const ALL: Items = Items(((1 << 52) - 1) << 1);
+
+    pub fn contains(&self, c: char) -> bool {
+        priority(c).is_some_and(|p| self.0 & 1 << p != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // The items of all rucksacks, or every item if there are none
+    pub fn common<I>(rucksacks: I) -> Items
+        where I: IntoIterator<Item=Items> {
+        rucksacks.into_iter().fold(Items::ALL, BitAnd::bitand)
+    }
+
+    pub fn priorities(self) -> impl Iterator<Item=u32> {
+        (1..=52).filter(move |p| self.0 & 1 << p != 0)
+    }
+
+    pub fn priority_sum(&self) -> u32 {
+        self.priorities().sum()
+    }
+
+    pub fn chars(self) -> impl Iterator<Item=char> {
+        self.priorities().map(item)
+    }
+}
+
+impl BitAnd for Items {
+    type Output = Items;
+
+    fn bitand(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+}
+
+impl BitOr for Items {
+    type Output = Items;
+
+    fn bitor(self, other: Items) -> Items {
+        Items(self.0 | other.0)
+    }
+}
+
+impl TryFrom<&str> for Items {
+    type Error = RucksackError;
+
+    fn try_from(s: &str) -> std::result::Result<Self, RucksackError> {
+        s.chars()
+            .enumerate()
+            .try_fold(Items::default(), |items, (i, c)| match priority(c) {
+                Some(p) => Ok(Items(items.0 | 1 << p)),
+                None => Err(RucksackError::InvalidItem { item: c, column: i + 1 }),
+            })
+    }
+}
+
+/// A rucksack with the items of both of its compartments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rucksack {
+    /// One based line of the rucksack in the input.
+    pub line: usize,
+    pub left: Items,
+    pub right: Items,
+}
+
+impl Rucksack {
+    pub fn parse(line: usize, s: &str) -> Result<Self> {
+        Items::try_from(s).map_err(|e| e.at_line(line))?;
+        if !s.len().is_multiple_of(2) {
+            return Err(RucksackError::OddLength { length: s.len() }.at_line(line));
+        }
+        // all items are ASCII, so the middle is a char boundary
+        let (left, right) = s.split_at(s.len() / 2);
+        Ok(Self {
+            line,
+            left: Items::try_from(left).expect("Items were checked above"),
+            right: Items::try_from(right).expect("Items were checked above"),
+        })
+    }
+
+    pub fn items(&self) -> Items {
+        self.left | self.right
+    }
+
+    // The items packed into both compartments
+    pub fn doubles(&self) -> Items {
+        self.left & self.right
+    }
 }
 
-fn calculate1(lines: &Vec<String>) -> u32 {
+pub fn read_rucksacks(lines: &[String]) -> Result<Vec<Rucksack>> {
     lines.iter()
-        .flat_map(find_doubles_in_backpack)
-        .map(priority)
-        .sum()
+        .enumerate()
+        .map(|(i, line)| Rucksack::parse(i + 1, line.strip_suffix('\r').unwrap_or(line)))
+        .collect()
 }
 
-fn calculate2(lines: &Vec<String>) -> u32 {
-    lines.chunks(3)
-        .flat_map(find_doubles_in_elves)
-        .map(priority)
+// The one item every rucksack of the group carries
+pub fn find_badge(group: &[Rucksack]) -> Result<char> {
+    let line = group.first().map_or(1, |rucksack| rucksack.line);
+    let common = Items::common(group.iter().map(Rucksack::items));
+    let mut badges = common.chars();
+    match (badges.next(), badges.next()) {
+        (Some(badge), None) => Ok(badge),
+        (None, _) => Err(RucksackError::NoBadge { size: group.len() }.at_line(line)),
+        (Some(_), Some(_)) => Err(RucksackError::MultipleBadges { badges: common.chars().collect() }.at_line(line)),
+    }
+}
+
+// Sum of the badge priorities of consecutive groups of `size` rucksacks
+pub fn badge_sum(rucksacks: &[Rucksack], size: usize) -> Result<u32> {
+    if size == 0 {
+        return Err(Error::solve("Groups need at least one elf"));
+    }
+    let mut sum = 0;
+    for group in rucksacks.chunks(size) {
+        if group.len() < size {
+            return Err(RucksackError::IncompleteGroup { size, found: group.len() }.at_line(group[0].line));
+        }
+        sum += priority(find_badge(group)?).expect("Badges are items");
+    }
+    Ok(sum)
+}
+
+pub fn double_sum(rucksacks: &[Rucksack]) -> u32 {
+    rucksacks.iter()
+        .map(|rucksack| rucksack.doubles().priority_sum())
         .sum()
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day3.txt");
-    if let Ok(lines) = lines {
-        let priority_sum = calculate1(&lines);
-        println!("Day 3: Priority Sum of duplicate items in rucksack = {priority_sum}");
-        let priority_sum = calculate2(&lines);
-        println!("Day 3: Priority Sum of duplicate items in group = {priority_sum}");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compartment {
+    Left,
+    Right,
+}
+
+impl Compartment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compartment::Left => "left",
+            Compartment::Right => "right",
+        }
+    }
+}
+
+/// All copies of one item moved out of a compartment into the other one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub item: char,
+    pub count: usize,
+    pub from: Compartment,
+}
+
+impl Move {
+    // every moved copy costs the priority of the item
+    pub fn cost(&self) -> u32 {
+        priority(self.item).expect("Only items are moved") * self.count as u32
+    }
+}
+
+/// A rucksack after its reorganization, with no item left in both compartments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// One based line of the rucksack in the input.
+    pub line: usize,
+    pub left: String,
+    pub right: String,
+    pub moves: Vec<Move>,
+}
+
+impl Layout {
+    pub fn move_count(&self) -> usize {
+        self.moves.iter().map(|m| m.count).sum()
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.moves.iter().map(Move::cost).sum()
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} from {}", self.count, self.item, self.from.name())
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} | {}", self.left, self.right)
+    }
+}
+
+// Moves every item found in both compartments into the one holding more copies of it, which
+// takes the fewest moves and, as all copies share a priority, also costs the least. On ties
+// the item goes left. Compartments keep the order of their items and get the moved ones appended.
+pub fn reorganize(line: usize, s: &str) -> Result<Layout> {
+    let rucksack = Rucksack::parse(line, s)?;
+    let (left, right) = s.split_at(s.len() / 2);
+    let moves = rucksack.doubles()
+        .chars()
+        .map(|item| {
+            let (in_left, in_right) = (left.matches(item).count(), right.matches(item).count());
+            if in_right > in_left {
+                Move { item, count: in_left, from: Compartment::Left }
+            } else {
+                Move { item, count: in_right, from: Compartment::Right }
+            }
+        })
+        .collect::<Vec<Move>>();
+    let moved = |c: char, from| moves.iter().any(|m| m.item == c && m.from == from);
+    let rearrange = |kept: &str, other: &str, side, other_side| kept.chars()
+        .filter(|c| !moved(*c, side))
+        .chain(other.chars().filter(|c| moved(*c, other_side)))
+        .collect::<String>();
+    Ok(Layout {
+        line,
+        left: rearrange(left, right, Compartment::Left, Compartment::Right),
+        right: rearrange(right, left, Compartment::Right, Compartment::Left),
+        moves,
+    })
+}
+
+pub fn plan_reorganization(lines: &[String]) -> Result<Vec<Layout>> {
+    lines.iter()
+        .enumerate()
+        .map(|(i, line)| reorganize(i + 1, line.strip_suffix('\r').unwrap_or(line)))
+        .collect()
+}
+
+pub fn calculate1(lines: &[String]) -> Result<u32> {
+    Ok(double_sum(&read_rucksacks(lines)?))
+}
+
+pub fn calculate2(lines: &[String]) -> Result<u32> {
+    badge_sum(&read_rucksacks(lines)?, 3)
+}
+
+#[derive(Debug, Clone)]
+pub struct Day3 {
+    group_size: usize,
+}
+
+impl Day3 {
+    pub fn with_group_size(group_size: usize) -> Self {
+        Self { group_size }
+    }
+}
+
+impl Default for Day3 {
+    fn default() -> Self {
+        Self::with_group_size(3)
+    }
+}
+
+impl Solution for Day3 {
+    type Input = Vec<Rucksack>;
+
+    const PART1: &'static str = "Priority Sum of duplicate items in rucksack";
+    const PART2: &'static str = "Priority Sum of duplicate items in group";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        read_rucksacks(&lines::collect(lines)?)
+    }
+
+    fn part1(&self, rucksacks: &Self::Input) -> Result<Answer> {
+        Ok(double_sum(rucksacks).into())
+    }
+
+    fn part2(&self, rucksacks: &Self::Input) -> Result<Answer> {
+        Ok(badge_sum(rucksacks, self.group_size)?.into())
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculate1, calculate2};
+    use super::{badge_sum, calculate1, calculate2, plan_reorganization, read_rucksacks, reorganize, Compartment, Items, Move, Rucksack, RucksackError};
 
     fn test_data () -> Vec<String> {
-        vec![
+        [
             "vJrwpWtwJgWrhcsFMMfFFhFp",
             "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
             "PmmdzqPrVvPwwTWBwg",
@@ -82,14 +360,79 @@ mod tests {
     #[test]
     fn test1() {
         let input = test_data();
-        let priority_sum = calculate1(&input);
+        let priority_sum = calculate1(&input).unwrap();
         assert_eq!(priority_sum, 157);
     }
 
     #[test]
     fn test2() {
         let input = test_data();
-        let priority_sum = calculate2(&input);
+        let priority_sum = calculate2(&input).unwrap();
         assert_eq!(priority_sum, 70);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_invalid_item() {
+        let input = ["vJrwpWtwJgWrhcsFMMfFFhFp", "jqHRNq1jqz"].map(str::to_string);
+        let error = read_rucksacks(&input).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(7)));
+        assert_eq!(error.downcast_ref(), Some(&RucksackError::InvalidItem { item: '1', column: 7 }));
+    }
+
+    #[test]
+    fn test_items() {
+        let items = Rucksack::parse(1, "vJrwpWtwJgWrhcsFMMfFFhFp").unwrap().doubles();
+        assert_eq!(items.priorities().collect::<Vec<u32>>(), vec![16]);
+        assert!(items.contains('p') && !items.contains('P') && !items.contains('1'));
+        let items = Items::try_from("aZ").unwrap();
+        assert_eq!((items.priority_sum(), items.chars().collect::<String>()), (53, "aZ".to_string()));
+        assert_eq!(Items::common([]), Items::ALL);
+        assert_eq!(Items::ALL.len(), 52);
+        assert!(Items::common(["ab", "bc", "cd"].map(|s| Items::try_from(s).unwrap())).is_empty());
+    }
+
+    #[test]
+    fn test_groups() {
+        let rucksacks = read_rucksacks(&test_data()).unwrap();
+        assert_eq!(badge_sum(&rucksacks, 3).unwrap(), 70);
+
+        let error = badge_sum(&rucksacks[..5], 3).unwrap_err();
+        assert_eq!(error.line(), Some(4));
+        assert_eq!(error.downcast_ref(), Some(&RucksackError::IncompleteGroup { size: 3, found: 2 }));
+        let error = badge_sum(&rucksacks, 1).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(RucksackError::MultipleBadges { .. })));
+        let error = badge_sum(&rucksacks, 6).unwrap_err();
+        assert_eq!(error.downcast_ref(), Some(&RucksackError::NoBadge { size: 6 }));
+        assert!(badge_sum(&rucksacks, 0).is_err());
+    }
+
+    #[test]
+    fn test_odd_length() {
+        let error = read_rucksacks(&["abcd", "abc"].map(str::to_string)).unwrap_err();
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(error.downcast_ref(), Some(&RucksackError::OddLength { length: 3 }));
+        assert_eq!(error.to_string(), "2: Rucksack of 3 items cannot be split into two compartments");
+    }
+
+    #[test]
+    fn test_reorganize() {
+        let layout = reorganize(7, "aAbBAcBB").unwrap();
+        assert_eq!(layout.to_string(), "aAbA | cBBB");
+        assert_eq!(layout.moves, vec![
+            Move { item: 'A', count: 1, from: Compartment::Right },
+            Move { item: 'B', count: 1, from: Compartment::Left },
+        ]);
+        assert_eq!((layout.line, layout.move_count(), layout.cost()), (7, 2, 27 + 28));
+        assert_eq!(layout.moves[0].to_string(), "1xA from right");
+
+        let layouts = plan_reorganization(&test_data()).unwrap();
+        let rucksacks = read_rucksacks(&test_data()).unwrap();
+        for (layout, rucksack) in layouts.iter().zip(&rucksacks) {
+            assert_eq!(layout.moves.len(), 1);
+            let left = Items::try_from(layout.left.as_str()).unwrap();
+            let right = Items::try_from(layout.right.as_str()).unwrap();
+            assert!((left & right).is_empty());
+            assert_eq!(rucksack.items(), left | right);
+        }
+    }
+}
@@ -1,65 +1,278 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::ops::RangeInclusive;
+use std::str::FromStr;
 
-use crate::lines;
+use crate::error::{Error, Result};
+use crate::interval::{Discrete, Interval, IntervalSet, IntervalTree};
+pub use crate::interval::{fully_contains, partly_contains};
+use crate::lines::{self, LineSource};
+use crate::solution::{Answer, Solution};
 
-fn fully_contains<T>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool
-    where T: PartialOrd {
-    r1.contains(r2.start()) && r1.contains(r2.end()) || r2.contains(r1.start()) && r2.contains(r1.end())
+fn parse_bound<T>(s: &str, line: usize, column: usize) -> Result<T>
+    where T: FromStr, T::Err: fmt::Display {
+    s.parse()
+        .map_err(|e| Error::parse(format!("Invalid section '{s}': {e}")).at(line, column))
 }
 
-fn partly_contains<T>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool
-    where T: PartialOrd {
-    r1.contains(r2.start()) || r1.contains(r2.end()) || r2.contains(r1.start()) || r2.contains(r1.end())
+// Parses `a-b`, the single section `a`, and the open ranges `a-` and `-b` up to the largest or
+// from the smallest section. Sections can be negative, e.g. `-3-5` or `-5--2`, but as `-5`
+// is the open range up to 5, the single section -5 has to be written `-5--5`.
+pub fn parse_range<T>(s: &str, line: usize, column: usize) -> Result<RangeInclusive<T>>
+    where T: Discrete + FromStr, T::Err: fmt::Display {
+    let is_bound = |s: &str| {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        s.is_empty() || !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    let separators = || s.match_indices('-').map(|(i, _)| i);
+    // the first '-' with a valid or missing bound on either side separates them; otherwise the
+    // first one after a valid start, so that errors point at the invalid bound
+    let separator = separators().find(|i| is_bound(&s[..*i]) && is_bound(&s[i + 1..]))
+        .or_else(|| separators().find(|i| is_bound(&s[..*i])))
+        .or_else(|| s.find('-'));
+    let (start, end) = match separator {
+        Some(i) if s.len() > 1 => (&s[..i], &s[i + 1..]),
+        None if !s.is_empty() => (s, s),
+        _ => return Err(Error::parse(format!("Expected a range like '2-4', '5', '5-' or '-9', found '{s}'")).at(line, column)),
+    };
+    let start = if start.is_empty() { T::MIN } else { parse_bound(start, line, column)? };
+    let end = if end.is_empty() { T::MAX } else { parse_bound(end, line, column + s.len() - end.len())? };
+    if start > end {
+        return Err(Error::parse(format!("Range '{s}' ends before it starts")).at(line, column));
+    }
+    Ok(start..=end)
 }
 
-fn get_sections(lines: &Vec<String>) -> Vec<(RangeInclusive<u32>, RangeInclusive<u32>)> {
-    lines.iter()
-        .map(|s| {
-            let mut split = s.split(",")
-                .map(|s| {
-                    let split = s.split("-")
-                        .filter_map(|s| s.parse().ok())
-                        .collect::<Vec<u32>>();
-                    assert_eq!(split.len(), 2);
-                    split[0]..=split[1]
-                })
-                .collect::<Vec<RangeInclusive<u32>>>();
-            assert_eq!(split.len(), 2);
-            (split.remove(0), split.remove(0))
+// Parses the comma separated ranges of a group of any number of elves
+pub fn parse_group<T>(s: &str, line: usize) -> Result<Vec<RangeInclusive<T>>>
+    where T: Discrete + FromStr, T::Err: fmt::Display {
+    let mut column = 1;
+    s.split(',')
+        .map(|range| {
+            let parsed = parse_range(range, line, column);
+            column += range.len() + 1;
+            parsed
         })
-        .collect::<Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>>()
+        .collect()
+}
+
+pub fn parse_groups<T>(lines: &[String]) -> Result<Vec<Vec<RangeInclusive<T>>>>
+    where T: Discrete + FromStr, T::Err: fmt::Display {
+    lines.iter()
+        .enumerate()
+        .map(|(i, s)| parse_group(s, i + 1))
+        .collect()
+}
+
+fn parse_pair(s: &str, line: usize) -> Result<(RangeInclusive<u32>, RangeInclusive<u32>)> {
+    let ranges = s.split(',').collect::<Vec<&str>>();
+    if ranges.len() != 2 {
+        // at the start of the line, or of the first range too many
+        let column = ranges.iter().take(2).map(|range| range.len() + 1).sum::<usize>() + 1;
+        let column = if ranges.len() < 2 { 1 } else { column };
+        return Err(Error::parse(format!("Expected two ranges separated by ',', found {}", ranges.len())).at(line, column));
+    }
+    Ok((parse_range(ranges[0], line, 1)?, parse_range(ranges[1], line, ranges[0].len() + 2)?))
+}
+
+pub fn get_sections(lines: &[String]) -> Result<Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>> {
+    lines.iter()
+        .enumerate()
+        .map(|(i, s)| parse_pair(s, i + 1))
+        .collect()
+}
+
+pub fn read_sections(lines: &mut dyn LineSource) -> Result<Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>> {
+    let mut sections = vec![];
+    lines::try_for_each(lines, |i, line| {
+        sections.push(parse_pair(line, i)?);
+        Ok(())
+    })?;
+    Ok(sections)
+}
+
+// Counts the fully and the partly contained pairs in one pass, without keeping any pair
+pub fn count_contained(lines: &mut dyn LineSource) -> Result<(usize, usize)> {
+    let mut counts = (0, 0);
+    lines::try_for_each(lines, |i, line| {
+        let (first, second) = parse_pair(line, i)?;
+        counts.0 += usize::from(fully_contains(&first, &second));
+        counts.1 += usize::from(partly_contains(&first, &second));
+        Ok(())
+    })?;
+    Ok(counts)
+}
+
+/// The assignment of one elf: the `position`th range, from 1, on `line` of the input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Assignment {
+    pub line: usize,
+    pub position: usize,
+    pub sections: RangeInclusive<i64>,
 }
 
-fn calculate1(sections: &Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>) -> usize {
-    sections.into_iter()
+/// Every assignment of the camp in an interval tree, for overlap queries across all pairs.
+#[derive(Debug, Clone)]
+pub struct Camp {
+    tree: IntervalTree<i64, Assignment>,
+}
+
+impl Camp {
+    pub fn new(pairs: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) -> Self {
+        let sections = |range: &RangeInclusive<u32>| i64::from(*range.start())..=i64::from(*range.end());
+        let groups = pairs.iter()
+            .map(|(first, second)| vec![sections(first), sections(second)])
+            .collect::<Vec<Vec<RangeInclusive<i64>>>>();
+        Self::from_groups(&groups)
+    }
+
+    // Groups of any size, numbering the elves of a group from 1
+    pub fn from_groups(groups: &[Vec<RangeInclusive<i64>>]) -> Self {
+        let tree = groups.iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.iter().enumerate().map(move |(j, sections)| Assignment {
+                line: i + 1,
+                position: j + 1,
+                sections: sections.clone(),
+            }))
+            .map(|assignment| (Interval::from(assignment.sections.clone()), assignment))
+            .collect();
+        Self { tree }
+    }
+
+    pub fn assignments(&self) -> impl Iterator<Item=&Assignment> {
+        self.tree.entries().iter().map(|(_, assignment)| assignment)
+    }
+
+    // The elves that clean `section`
+    pub fn covering(&self, section: i64) -> Vec<&Assignment> {
+        self.tree.containing(section)
+            .into_iter()
+            .map(|(_, assignment)| assignment)
+            .collect()
+    }
+
+    // The assignments sharing at least one section with `assignment`, other than itself
+    pub fn overlapping(&self, assignment: &Assignment) -> Vec<&Assignment> {
+        self.tree.overlapping(Interval::from(assignment.sections.clone()))
+            .into_iter()
+            .map(|(_, other)| other)
+            .filter(|other| *other != assignment)
+            .collect()
+    }
+
+    // The sections between the lowest and the highest assigned one that nobody cleans
+    pub fn uncovered(&self) -> IntervalSet<i64> {
+        self.tree.covered().gaps()
+    }
+
+    // The sections cleaned by at least one elf
+    pub fn covered(&self) -> &IntervalSet<i64> {
+        self.tree.covered()
+    }
+
+    // The sections cleaned by more than one elf, found by sweeping over all range ends
+    pub fn multiply_covered(&self) -> IntervalSet<i64> {
+        let mut changes = BTreeMap::<i128, i64>::new();
+        for (interval, _) in self.tree.entries() {
+            *changes.entry(i128::from(interval.start())).or_default() += 1;
+            *changes.entry(i128::from(interval.end()) + 1).or_default() -= 1;
+        }
+        let mut multiply_covered = IntervalSet::new();
+        let mut elves = 0;
+        let mut changes = changes.into_iter().peekable();
+        while let Some((section, change)) = changes.next() {
+            elves += change;
+            if let (true, Some((next, _))) = (elves > 1, changes.peek()) {
+                // `next - 1` is a section, as some range ends there
+                multiply_covered.insert(Interval::new(section as i64, (next - 1) as i64));
+            }
+        }
+        multiply_covered
+    }
+
+    // A smallest set of assignments that still covers every covered section. Within every
+    // run of covered sections it greedily takes the assignment reaching furthest, which is
+    // optimal for intervals. Ordered by the first section of the assignments.
+    pub fn minimal_cover(&self) -> Vec<&Assignment> {
+        let entries = self.tree.entries();
+        let mut cover = vec![];
+        let mut i = 0;
+        for run in self.covered().intervals() {
+            let mut next = i128::from(run.start());
+            while next <= i128::from(run.end()) {
+                // earlier candidates end before `next`, or they would have been taken
+                let mut best: Option<&(Interval<i64>, Assignment)> = None;
+                while i < entries.len() && i128::from(entries[i].0.start()) <= next {
+                    if best.is_none_or(|best| entries[i].0.end() > best.0.end()) {
+                        best = Some(&entries[i]);
+                    }
+                    i += 1;
+                }
+                let (interval, assignment) = best.expect("Every covered section is in some assignment");
+                cover.push(assignment);
+                next = i128::from(interval.end()) + 1;
+            }
+        }
+        cover
+    }
+
+    // The elves that are not needed to cover the same sections, in input order
+    pub fn releasable(&self) -> Vec<&Assignment> {
+        let kept = self.minimal_cover().into_iter().collect::<HashSet<&Assignment>>();
+        let mut releasable = self.assignments()
+            .filter(|assignment| !kept.contains(assignment))
+            .collect::<Vec<&Assignment>>();
+        releasable.sort_by_key(|assignment| (assignment.line, assignment.position));
+        releasable
+    }
+}
+
+pub fn calculate1(sections: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) -> usize {
+    sections.iter()
         .filter(|s| fully_contains(&s.0, &s.1))
         .count()
 }
 
-fn calculate2(sections: &Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>) -> usize {
-    sections.into_iter()
+pub fn calculate2(sections: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) -> usize {
+    sections.iter()
         .filter(|s| partly_contains(&s.0, &s.1))
         .count()
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day4.txt");
-    if let Ok(lines) = lines {
-        let sections = get_sections(&lines);
-        let contained = calculate1(&sections);
-        println!("Day 4: Count of fully contained sections = {contained}");
-        let contained = calculate2(&sections);
-        println!("Day 4: Count of partly contained sections = {contained}");
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>;
+
+    const PART1: &'static str = "Count of fully contained sections";
+    const PART2: &'static str = "Count of partly contained sections";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        read_sections(lines)
+    }
+
+    fn part1(&self, sections: &Self::Input) -> Result<Answer> {
+        Ok(calculate1(sections).into())
+    }
+
+    fn part2(&self, sections: &Self::Input) -> Result<Answer> {
+        Ok(calculate2(sections).into())
+    }
+
+    fn stream(&self, lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>> {
+        Some(count_contained(lines).map(|(fully, partly)| (fully.into(), partly.into())))
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculate1, calculate2, get_sections};
+    use crate::lines::SliceLines;
+    use crate::interval::Interval;
+    use super::{calculate1, calculate2, count_contained, get_sections, parse_group, parse_groups, parse_range, Camp};
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "2-4,6-8",
             "2-3,4-5",
             "5-7,7-9",
@@ -72,7 +285,7 @@ mod tests {
     #[test]
     fn test1() {
         let input = test_data();
-        let sections = get_sections(&input);
+        let sections = get_sections(&input).unwrap();
         let contained = calculate1(&sections);
         assert_eq!(contained, 2);
     }
@@ -80,8 +293,90 @@ mod tests {
     #[test]
     fn test2() {
         let input = test_data();
-        let sections = get_sections(&input);
+        let sections = get_sections(&input).unwrap();
         let priority_sum = calculate2(&sections);
         assert_eq!(priority_sum, 4);
+        assert_eq!(count_contained(&mut SliceLines::new(&input)).unwrap(), (2, 4));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_errors() {
+        let input = ["2-4,6-8", "2-3,4-x"].map(str::to_string);
+        let error = get_sections(&input).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(7)));
+        let error = get_sections(&["2-4;6-8".to_string()]).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(1)));
+    }
+
+    #[test]
+    fn test_camp() {
+        let camp = Camp::new(&get_sections(&test_data()).unwrap());
+        assert_eq!(camp.assignments().count(), 12);
+        let covering = camp.covering(7).iter()
+            .map(|assignment| (assignment.line, assignment.position))
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(covering, vec![(4, 1), (4, 2), (6, 2), (3, 1), (1, 2), (3, 2)]);
+        let assignment = camp.assignments().find(|assignment| assignment.line == 2 && assignment.position == 2).unwrap();
+        assert_eq!(camp.overlapping(assignment).len(), 7);
+        assert!(camp.uncovered().is_empty());
+
+        let camp = Camp::new(&get_sections(&["1-3,8-9".to_string(), "2-4,12-12".to_string()]).unwrap());
+        assert_eq!(camp.uncovered().intervals(), &[Interval::new(5, 7), Interval::new(10, 11)]);
+        assert!(camp.covering(6).is_empty());
+    }
+
+    #[test]
+    fn test_coverage() {
+        let camp = Camp::new(&get_sections(&test_data()).unwrap());
+        assert_eq!(camp.covered().intervals(), &[Interval::new(2, 9)]);
+        assert_eq!(camp.multiply_covered().intervals(), &[Interval::new(2, 8)]);
+        let cover = camp.minimal_cover().iter()
+            .map(|assignment| (assignment.line, assignment.position))
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(cover, vec![(4, 1), (3, 2)]);
+        assert_eq!(camp.releasable().len(), 10);
+
+        let camp = Camp::new(&get_sections(&["1-3,3-3".to_string(), "5-6,6-9".to_string(), "1-3,7-7".to_string()]).unwrap());
+        assert_eq!(camp.covered().intervals(), &[Interval::new(1, 3), Interval::new(5, 9)]);
+        assert_eq!(camp.multiply_covered().intervals(), &[Interval::new(1, 3), Interval::new(6, 7)]);
+        assert_eq!(camp.minimal_cover().len(), 3);
+        let releasable = camp.releasable().iter()
+            .map(|assignment| (assignment.line, assignment.position))
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(releasable, vec![(1, 2), (3, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range::<i32>("-3-5", 1, 1).unwrap(), -3..=5);
+        assert_eq!(parse_range::<i32>("5--2", 1, 1).unwrap_err().reason(), "Range '5--2' ends before it starts");
+        assert_eq!(parse_range::<i64>("-7--2", 1, 1).unwrap(), -7..=-2);
+        assert_eq!(parse_range::<i8>("--9", 1, 1).unwrap(), i8::MIN..=-9);
+        assert_eq!(parse_range::<u32>("5", 1, 1).unwrap(), 5..=5);
+        assert_eq!(parse_range::<u32>("5-", 1, 1).unwrap(), 5..=u32::MAX);
+        assert_eq!(parse_range::<u32>("-9", 1, 1).unwrap(), 0..=9);
+        assert_eq!(parse_range::<i16>("-5--5", 1, 1).unwrap(), -5..=-5);
+
+        let group = parse_group::<u64>("2-4,6,8-,-1", 1).unwrap();
+        assert_eq!(group, vec![2..=4, 6..=6, 8..=u64::MAX, 0..=1]);
+        let groups = parse_groups::<i32>(&["1-2,3-4,-5--4".to_string(), "7".to_string()]).unwrap();
+        assert_eq!(groups, vec![vec![1..=2, 3..=4, -5..=-4], vec![7..=7]]);
+        let camp = Camp::from_groups(&parse_groups(&["1-2,2-4,9".to_string()]).unwrap());
+        assert_eq!(camp.covering(2).iter().map(|assignment| assignment.position).collect::<Vec<usize>>(), vec![1, 2]);
+        assert_eq!(camp.uncovered().covered(), 4);
+        let camp = Camp::from_groups(&parse_groups(&["-5-3,2-4,6".to_string()]).unwrap());
+        assert_eq!(camp.covered().intervals(), &[Interval::new(-5, 4), Interval::new(6, 6)]);
+        assert_eq!(camp.multiply_covered().intervals(), &[Interval::new(2, 3)]);
+        assert_eq!(camp.covering(-1).len(), 1);
+
+        let error = parse_group::<u32>("1-2,-3-4", 3).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(3), Some(5)));
+        let error = parse_group::<u8>("1-2,3-300", 1).unwrap_err();
+        assert_eq!(error.column(), Some(7));
+        for invalid in ["", "-", "1-2-3", "a", "x-1"] {
+            assert!(parse_range::<i32>(invalid, 1, 1).is_err(), "{invalid}");
+        }
+        let error = get_sections(&["1-2,3-4,5-6".to_string()]).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(9)));
+    }
+}
@@ -1,151 +1,340 @@
 use std::collections::{BTreeMap, VecDeque};
-use crate::lines;
+use std::fmt;
+use std::str::FromStr;
+use crate::error::{Error, Result};
+use crate::lines::{self, LineSource, Section};
+use crate::solution::{Answer, Solution};
 
 #[derive(Debug)]
-struct MoveInstruction {
-    count: u32,
-    from_stack: String,
-    to_stack: String,
+pub struct MoveInstruction {
+    pub count: u32,
+    pub from_stack: String,
+    pub to_stack: String,
 }
 
-#[derive(Debug, Clone)]
-struct Stack {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
     names: Vec<String>,
     stack: BTreeMap<String, VecDeque<String>>,
 }
 
+fn stack_mut<'a>(stack: &'a mut BTreeMap<String, VecDeque<String>>, name: &String) -> Result<&'a mut VecDeque<String>> {
+    stack.get_mut(name)
+        .ok_or_else(|| Error::solve(format!("Unknown stack {name}")))
+}
+
+fn pop_crate(stack: &mut BTreeMap<String, VecDeque<String>>, name: &String) -> Result<String> {
+    stack_mut(stack, name)?
+        .pop_front()
+        .ok_or_else(|| Error::solve(format!("No more Crates on Stack {name}")))
+}
+
 impl Stack {
-    fn move_crates(&mut self, instructions: &Vec<MoveInstruction>) {
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn crates(&self, name: &str) -> Option<&VecDeque<String>> {
+        self.stack.get(name)
+    }
+
+    pub fn move_crates(&mut self, instructions: &Vec<MoveInstruction>) -> Result<()> {
         let stack = &mut self.stack;
         for instruction in instructions {
             let mut i = 0;
             while i < instruction.count {
-                let from = stack.get_mut(&instruction.from_stack).unwrap();
-                let crate_name = from.pop_front().expect("No more Crates on Stack");
+                let crate_name = pop_crate(stack, &instruction.from_stack)?;
                 // println!("Moving {crate_name} from {} to {} ({}/{})", instruction.from_stack, instruction.to_stack, i + 1, instruction.count);
-                let to = stack.get_mut(&instruction.to_stack).unwrap();
-                to.push_front(crate_name);
+                stack_mut(stack, &instruction.to_stack)?.push_front(crate_name);
                 // println!("{:?}", stack);
                 i += 1;
             }
         }
+        Ok(())
     }
 
-    fn move_stacked_crates(&mut self, instructions: &Vec<MoveInstruction>) {
+    pub fn move_stacked_crates(&mut self, instructions: &Vec<MoveInstruction>) -> Result<()> {
         let stack = &mut self.stack;
         for instruction in instructions {
             let mut i = 0;
             let mut crates= vec![];
             while i < instruction.count {
-                let from = stack.get_mut(&instruction.from_stack).unwrap();
-                let crate_name = from.pop_front().expect("No more Crates on Stack");
+                let crate_name = pop_crate(stack, &instruction.from_stack)?;
                 crates.insert(0, crate_name);
                 // println!("Moving {crate_name} from {} to {} ({}/{})", instruction.from_stack, instruction.to_stack, i + 1, instruction.count);
                 // println!("{:?}", stack);
                 i += 1;
             }
-            let to = stack.get_mut(&instruction.to_stack).unwrap();
+            let to = stack_mut(stack, &instruction.to_stack)?;
             for crate_name in crates {
                 to.push_front(crate_name);
             }
         }
+        Ok(())
     }
 
-    fn get_top(&self) -> String {
+    pub fn get_top(&self) -> Result<String> {
         self.names.iter()
-            .fold(String::new(), |accu, item| accu + self.stack.get(item).unwrap().front().unwrap())
+            .try_fold(String::new(), |accu, item| {
+                let top = self.stack.get(item)
+                    .and_then(VecDeque::front)
+                    .ok_or_else(|| Error::solve(format!("Stack {item} is empty")))?;
+                Ok(accu + top)
+            })
     }
 }
 
-impl From<&String> for MoveInstruction {
-    fn from(s: &String) -> Self {
-        let parts = s.split(" ")
+impl Stack {
+    // The drawing of the puzzle input: one row per level of crates, the highest first, and the
+    // labels below. Every stack is as wide as its widest crate or label, but at least as wide
+    // as a crate of one letter, with crates and labels centered. All rows are padded to the
+    // full width, so `prepare_stack` reads back the same stacks.
+    pub fn render(&self) -> Vec<String> {
+        let widths = self.names.iter()
+            .map(|name| self.stack[name].iter()
+                .map(|item| item.chars().count() + 2)
+                .fold(name.chars().count().max(3), usize::max))
+            .collect::<Vec<usize>>();
+        let height = self.stack.values().map(VecDeque::len).max().unwrap_or(0);
+        let row = |cell: &dyn Fn(&String) -> String| self.names.iter()
+            .zip(&widths)
+            .map(|(name, width)| {
+                let text = cell(name);
+                let left = (width - text.chars().count()) / 2;
+                format!("{}{text}{}", " ".repeat(left), " ".repeat(width - left - text.chars().count()))
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let mut lines = (0..height).rev()
+            .map(|level| row(&|name| {
+                let crates = &self.stack[name];
+                // the front of the deque is the top of the stack
+                crates.len().checked_sub(level + 1)
+                    .map_or(String::new(), |i| format!("[{}]", crates[i]))
+            }))
+            .collect::<Vec<String>>();
+        lines.push(row(&|name| name.clone()));
+        lines
+    }
+}
+
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render().join("\n"))
+    }
+}
+
+impl FromStr for MoveInstruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts = s.split(' ')
             .collect::<Vec<&str>>();
-        assert_eq!(parts.len(), 6);
-        Self {
-            count: parts[1].parse().unwrap(),
+        if parts.len() != 6 || parts[0] != "move" || parts[2] != "from" || parts[4] != "to" {
+            return Err(Error::parse(format!("Expected 'move N from A to B', found '{s}'")));
+        }
+        Ok(Self {
+            count: parts[1].parse().map_err(|e| Error::parse(format!("Invalid crate count '{}': {e}", parts[1])))?,
             from_stack: parts[3].to_string(),
             to_stack: parts[5].to_string(),
+        })
+    }
+}
+
+// Splits the input into the drawing and the procedure
+pub fn prepare_lines(lines: &[String]) -> Result<(Section<'_>, Section<'_>)> {
+    let sections = lines::expect_sections(lines, 2)?;
+    Ok((sections[0], sections[1]))
+}
+
+// columns between tab stops, the width of one stack in the puzzle drawing
+const TAB_WIDTH: usize = 4;
+
+// The characters of a drawing line with their one based column in the line, tabs expanded to
+// the next tab stop and trailing whitespace removed
+fn grid_cells(line: &str) -> Vec<(char, usize)> {
+    let mut cells = vec![];
+    for (i, c) in line.trim_end().chars().enumerate() {
+        if c == '\t' {
+            let width = TAB_WIDTH - cells.len() % TAB_WIDTH;
+            cells.extend(std::iter::repeat_n((' ', i + 1), width));
+        } else {
+            cells.push((c, i + 1));
         }
     }
+    cells
+}
+
+// A stack label or a crate, covering the grid cells `start..end`
+struct Token {
+    text: String,
+    start: usize,
+    end: usize,
+    // one based column of the first character in the line
+    column: usize,
 }
 
-fn prepare_lines(lines: &Vec<String>) -> (&[String], &[String]) {
-    let mut split = lines.split(|s| s.is_empty()).collect::<Vec<&[String]>>();
-    assert_eq!(split.len(), 2);
-    (split.remove(0), split.remove(0))
+fn labels(cells: &[(char, usize)], line: usize) -> Result<Vec<Token>> {
+    let mut labels: Vec<Token> = vec![];
+    let mut start = None;
+    for i in 0..=cells.len() {
+        match (start, cells.get(i).is_some_and(|(c, _)| !c.is_whitespace())) {
+            (None, true) => start = Some(i),
+            (Some(first), false) => {
+                if let Some((c, column)) = cells[first..i].iter().find(|(c, _)| !c.is_alphanumeric()) {
+                    return Err(Error::parse(format!("Expected a row of stack labels, found '{c}'")).at(line, *column));
+                }
+                let text = cells[first..i].iter().map(|(c, _)| c).collect::<String>();
+                if labels.iter().any(|label| label.text == text) {
+                    return Err(Error::parse(format!("Duplicate stack label {text}")).at(line, cells[first].1));
+                }
+                labels.push(Token { text, start: first, end: i, column: cells[first].1 });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if labels.is_empty() {
+        return Err(Error::parse("Expected a row of stack labels").at_line(line));
+    }
+    Ok(labels)
 }
 
-fn prepare_stack(stack_lines: &[String]) -> Stack {
-    let mut stack_lines = stack_lines.to_vec();
-    stack_lines.reverse();
-    let name_str = stack_lines.remove(0);
+fn crates(cells: &[(char, usize)], line: usize) -> Result<Vec<Token>> {
+    let mut crates = vec![];
     let mut i = 0;
-    let mut names = vec![];
-    while i < name_str.len() {
-        names.push(name_str[i + 1..i + 2].to_string());
-        i += 4;
-    }
-    let mut stack = BTreeMap::new();
-    for name in &names {
-        stack.insert(name.clone(), VecDeque::new());
-    }
-    for line in stack_lines {
-        let mut i = 0;
-        let mut n = 0;
-        while i < line.len() {
-            let item = String::from(&line[i + 1..i + 2]);
-            if item != " " {
-                let name = names.get(n).unwrap();
-                stack.get_mut(name).unwrap().push_front(item.clone());
+    while i < cells.len() {
+        let (c, column) = cells[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c != '[' {
+            return Err(Error::parse(format!("Expected a crate like [A], found '{c}'")).at(line, column));
+        }
+        let len = cells[i + 1..].iter()
+            .position(|(c, _)| *c == ']' || *c == '[' || c.is_whitespace())
+            .filter(|len| cells[i + 1 + len].0 == ']')
+            .ok_or_else(|| Error::parse("Unterminated crate, expected ']'").at(line, column))?;
+        if len == 0 {
+            return Err(Error::parse("Crate without a name").at(line, column));
+        }
+        let text = cells[i + 1..i + 1 + len].iter().map(|(c, _)| c).collect();
+        crates.push(Token { text, start: i, end: i + len + 2, column });
+        i += len + 2;
+    }
+    Ok(crates)
+}
+
+// Reads the drawing with the stack labels in its last row. Every crate belongs to the one
+// label its brackets overlap, so labels and crates can be of any width. first_line is the one
+// based line number of the top row, used to position errors.
+pub fn prepare_stack(stack_lines: &[String], first_line: usize) -> Result<Stack> {
+    let (label_row, crate_rows) = stack_lines.split_last()
+        .ok_or_else(|| Error::parse("Missing stack drawing"))?;
+    let labels = labels(&grid_cells(label_row), first_line + crate_rows.len())?;
+    let names = labels.iter()
+        .map(|label| label.text.clone())
+        .collect::<Vec<String>>();
+    let mut stack = names.iter()
+        .map(|name| (name.clone(), VecDeque::new()))
+        .collect::<BTreeMap<String, VecDeque<String>>>();
+    // from the bottom row up, so every crate needs one below it
+    for (height, (j, row)) in crate_rows.iter().enumerate().rev().enumerate() {
+        let line = first_line + j;
+        let mut filled = vec![false; labels.len()];
+        for item in crates(&grid_cells(row), line)? {
+            let mut overlapping = labels.iter()
+                .enumerate()
+                .filter(|(_, label)| label.start < item.end && item.start < label.end);
+            let n = match (overlapping.next(), overlapping.next()) {
+                (Some((n, _)), None) => n,
+                _ => return Err(Error::parse(format!("Crate [{}] is not aligned with exactly one stack label", item.text))
+                    .at(line, item.column)),
+            };
+            if filled[n] {
+                return Err(Error::parse(format!("Crate [{}] shares stack {} with another crate of its row", item.text, names[n]))
+                    .at(line, item.column));
             }
-            i += 4;
-            n += 1;
+            filled[n] = true;
+            let crates = stack.get_mut(&names[n]).expect("Every label has a stack");
+            if crates.len() != height {
+                return Err(Error::parse(format!("Crate [{}] floats above an empty spot of stack {}", item.text, names[n]))
+                    .at(line, item.column));
+            }
+            crates.push_front(item.text);
         }
     }
-    // println!("{:?}", stack);
-    Stack {
+    Ok(Stack {
         names,
         stack,
-    }
+    })
 }
 
-fn prepare_instructions(lines: &[String]) -> Vec<MoveInstruction> {
-    lines.iter()
-        .map(<&String>::into)
+// first_line is the one based line number of the first instruction, used to position errors.
+// Lines may still end in the '\r' of CRLF endings, as in `Section::lines`. Every instruction
+// must name stacks of the drawing.
+pub fn prepare_instructions(lines: &[String], first_line: usize, stack: &Stack) -> Result<Vec<MoveInstruction>> {
+    Section { first_line, lines }.numbered()
+        .map(|(i, s)| {
+            let instruction: MoveInstruction = s.parse().map_err(|e: Error| e.at_line(i))?;
+            // the stacks are the fourth and sixth word of the line
+            for (name, word) in [(&instruction.from_stack, 3), (&instruction.to_stack, 5)] {
+                if !stack.names().contains(name) {
+                    let column = s.split(' ').take(word).map(|part| part.chars().count() + 1).sum::<usize>() + 1;
+                    return Err(Error::parse(format!("Unknown stack {name}")).at(i, column));
+                }
+            }
+            Ok(instruction)
+        })
         .collect()
 }
 
-fn calculate1(stack: &mut Stack, instructions: &Vec<MoveInstruction>) -> String {
-    stack.move_crates(instructions);
+pub fn calculate1(stack: &mut Stack, instructions: &Vec<MoveInstruction>) -> Result<String> {
+    stack.move_crates(instructions)?;
     stack.get_top()
 }
 
-fn calculate2(stack: &mut Stack, instructions: &Vec<MoveInstruction>) -> String {
-    stack.move_stacked_crates(instructions);
+pub fn calculate2(stack: &mut Stack, instructions: &Vec<MoveInstruction>) -> Result<String> {
+    stack.move_stacked_crates(instructions)?;
     stack.get_top()
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day5.txt");
-    if let Ok(lines) = lines {
-        let (stack, procedure) = prepare_lines(&lines);
-        let mut stack = prepare_stack(&stack);
-        let procedure = prepare_instructions(&procedure);
-        let contained = calculate1(&mut stack.clone(), &procedure);
-        println!("Day 5: Top of Stack (CrateMover 9000) = {contained}");
-        let contained = calculate2(&mut stack, &procedure);
-        println!("Day 5: Top of Stack (CrateMover 9001) = {contained}");
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = (Stack, Vec<MoveInstruction>);
+
+    const PART1: &'static str = "Top of Stack (CrateMover 9000)";
+    const PART2: &'static str = "Top of Stack (CrateMover 9001)";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        let lines = lines::collect(lines)?;
+        let (stack, procedure) = prepare_lines(&lines)?;
+        let stack = prepare_stack(stack.lines, stack.first_line)?;
+        let instructions = prepare_instructions(procedure.lines, procedure.first_line, &stack)?;
+        Ok((stack, instructions))
+    }
+
+    fn part1(&self, (stack, procedure): &Self::Input) -> Result<Answer> {
+        calculate1(&mut stack.clone(), procedure).map(Answer::from)
+    }
+
+    fn part2(&self, (stack, procedure): &Self::Input) -> Result<Answer> {
+        calculate2(&mut stack.clone(), procedure).map(Answer::from)
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
     use super::{calculate1, calculate2, prepare_stack, prepare_lines, prepare_instructions};
 
+    fn drawing(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(<&str>::to_string).collect()
+    }
+
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "    [D]    ",
             "[N] [C]    ",
             "[Z] [M] [P]",
@@ -161,11 +350,11 @@ mod tests {
     #[test]
     fn test1() {
         let input = test_data();
-        let lines = prepare_lines(&input);
-        let mut stack = prepare_stack(&lines.0);
+        let lines = prepare_lines(&input).unwrap();
+        let mut stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
         // println!("{:?}", stack.stack);
-        let procedure = prepare_instructions(&lines.1);
-        let x = calculate1(&mut stack, &procedure);
+        let procedure = prepare_instructions(lines.1.lines, lines.1.first_line, &stack).unwrap();
+        let x = calculate1(&mut stack, &procedure).unwrap();
         // println!("{:?}", stack.stack);
         assert_eq!(x, "CMZ");
     }
@@ -173,10 +362,126 @@ mod tests {
     #[test]
     fn test2() {
         let input = test_data();
-        let lines = prepare_lines(&input);
-        let mut stack = prepare_stack(&lines.0);
-        let procedure = prepare_instructions(&lines.1);
-        let x = calculate2(&mut stack, &procedure);
+        let lines = prepare_lines(&input).unwrap();
+        let mut stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
+        let procedure = prepare_instructions(lines.1.lines, lines.1.first_line, &stack).unwrap();
+        let x = calculate2(&mut stack, &procedure).unwrap();
         assert_eq!(x, "MCD");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_errors() {
+        let mut input = test_data();
+        input[7] = "move two from 2 to 1".to_string();
+        let lines = prepare_lines(&input).unwrap();
+        let stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
+        let error = prepare_instructions(lines.1.lines, lines.1.first_line, &stack).unwrap_err();
+        assert_eq!(error.line(), Some(8));
+        let error = prepare_instructions(&["move 1 from 9 to 2".to_string()], 6, &stack).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(6), Some(13)));
+        assert_eq!(error.reason(), "Unknown stack 9");
+        let error = prepare_instructions(&["move 1 from 2 to 10".to_string()], 6, &stack).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(6), Some(18)));
+
+        let input = test_data();
+        let lines = prepare_lines(&input).unwrap();
+        let mut stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
+        let procedure = prepare_instructions(&["move 4 from 2 to 1".to_string()], 1, &stack).unwrap();
+        assert!(calculate1(&mut stack, &procedure).is_err());
+
+        let mut input = test_data();
+        input.insert(7, " ".to_string());
+        let error = prepare_lines(&input).unwrap_err();
+        assert_eq!(error.line(), Some(9));
+        let error = prepare_lines(&input[..4]).unwrap_err();
+        assert_eq!(error.line(), Some(4));
+
+        let input = test_data().into_iter()
+            .map(|line| line + "\r")
+            .collect::<Vec<String>>();
+        let lines = prepare_lines(&input).unwrap();
+        let mut stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
+        let procedure = prepare_instructions(lines.1.lines, lines.1.first_line, &stack).unwrap();
+        assert_eq!(procedure[0].to_stack, "1");
+        assert_eq!(calculate1(&mut stack, &procedure).unwrap(), "CMZ");
+    }
+
+    #[test]
+    fn test_drawing() {
+        // ten stacks, the last one with a two digit label, ragged and with a trailing tab
+        let stack = prepare_stack(&drawing(&[
+            "                                    [J]",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [K]\t",
+            " 1   2   3   4   5   6   7   8   9   10 ",
+        ]), 1).unwrap();
+        assert_eq!(stack.names().len(), 10);
+        assert_eq!(stack.crates("10").unwrap(), &["J", "K"]);
+        assert_eq!(stack.get_top().unwrap(), "ABCDEFGHIJ");
+
+        // crates with longer names, and a tab standing for one empty stack
+        let stack = prepare_stack(&drawing(&[
+            "\t[Fe]",
+            "[Cu] [Ag]  [Au]",
+            " 29   47    79",
+        ]), 1).unwrap();
+        assert_eq!(stack.names(), &["29", "47", "79"]);
+        assert_eq!(stack.crates("47").unwrap(), &["Fe", "Ag"]);
+        assert_eq!(stack.get_top().unwrap(), "CuFeAu");
+    }
+
+    #[test]
+    fn test_drawing_errors() {
+        let error = prepare_stack(&drawing(&["  [A]", " 1   2"]), 1).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(3)));
+        assert_eq!(error.reason(), "Crate [A] is not aligned with exactly one stack label");
+        let error = prepare_stack(&drawing(&["[A]", "    [B]", " 1   2"]), 1).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(1)));
+        let error = prepare_stack(&drawing(&["[A] [B", " 1   2"]), 1).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(5)));
+        let error = prepare_stack(&drawing(&["[A] x", " 1   2"]), 1).unwrap_err();
+        assert_eq!(error.column(), Some(5));
+        let error = prepare_stack(&drawing(&["[A]", " 1   1"]), 1).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(6)));
+        assert!(prepare_stack(&drawing(&["[A]", "   "]), 1).is_err());
+        let error = prepare_stack(&drawing(&["[A] [B]", "[C] [D]"]), 1).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(1)));
+        assert_eq!(error.reason(), "Expected a row of stack labels, found '['");
+
+        // the drawing starts below the leading blank lines
+        let input = drawing(&["", "", "  [A]", " 1   2", "", "move 1 from 1 to 2"]);
+        let lines = prepare_lines(&input).unwrap();
+        let error = prepare_stack(lines.0.lines, lines.0.first_line).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(3), Some(3)));
+    }
+
+    #[test]
+    fn test_render() {
+        let input = test_data();
+        let lines = prepare_lines(&input).unwrap();
+        let mut stack = prepare_stack(lines.0.lines, lines.0.first_line).unwrap();
+        assert_eq!(stack.render(), lines.0.lines);
+
+        let procedure = prepare_instructions(&lines.1.lines[..2], lines.1.first_line, &stack).unwrap();
+        stack.move_crates(&procedure).unwrap();
+        assert_eq!(stack.to_string(), [
+            "        [Z]",
+            "        [N]",
+            "    [C] [D]",
+            "    [M] [P]",
+            " 1   2   3 ",
+        ].join("\n"));
+        assert_eq!(prepare_stack(&stack.render(), 1).unwrap(), stack);
+
+        let stack = prepare_stack(&drawing(&[
+            "\t[Fe]",
+            "[Cu] [Ag]  [Au]",
+            " 29   47    79",
+        ]), 1).unwrap();
+        assert_eq!(stack.render(), drawing(&[
+            "     [Fe]     ",
+            "[Cu] [Ag] [Au]",
+            " 29   47   79 ",
+        ]));
+        assert_eq!(prepare_stack(&stack.render(), 1).unwrap(), stack);
+    }
+}
@@ -1,9 +1,11 @@
 use std::collections::HashSet;
-use crate::lines;
+use crate::error::{Error, Result};
+use crate::lines::{self, LineSource};
+use crate::solution::{Answer, Solution};
 
-fn find_marker(input: &String, len: usize) -> Option<usize> {
+pub fn find_marker(input: &str, len: usize) -> Option<usize> {
     let mut i = 0;
-    while i < input.len() - len {
+    while i + len <= input.len() {
         let chars = &input[i..=i + len - 1];
         let uniq_chars = chars.chars().collect::<HashSet<char>>();
         if uniq_chars.len() == len {
@@ -14,43 +16,66 @@ fn find_marker(input: &String, len: usize) -> Option<usize> {
     None
 }
 
-fn find_packet_marker(input: &String) -> Option<usize> {
+pub fn find_packet_marker(input: &str) -> Option<usize> {
     find_marker(input, 4)
 }
 
-fn find_message_marker(input: &String) -> Option<usize> {
+pub fn find_message_marker(input: &str) -> Option<usize> {
     find_marker(input, 14)
 }
 
-fn calculate1(input: &Vec<String>) -> Vec<usize> {
+pub fn validate_signals(input: &[String]) -> Result<()> {
+    for (i, line) in input.iter().enumerate() {
+        if let Some((column, c)) = line.chars().enumerate().find(|(_, c)| !c.is_ascii()) {
+            return Err(Error::parse(format!("Invalid signal character '{c}'")).at(i + 1, column + 1));
+        }
+    }
+    Ok(())
+}
+
+pub fn calculate1(input: &[String]) -> Vec<usize> {
     input.iter()
+        .map(String::as_str)
         .filter_map(find_packet_marker)
         .collect::<Vec<usize>>()
 }
 
-fn calculate2(input: &Vec<String>) -> Vec<usize> {
+pub fn calculate2(input: &[String]) -> Vec<usize> {
     input.iter()
+        .map(String::as_str)
         .filter_map(find_message_marker)
         .collect::<Vec<usize>>()
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day6.txt");
-    if let Ok(lines) = lines {
-        let results = calculate1(&lines);
-        results.iter().for_each(|s| println!("Day 6: Start position of Packet is {s}"));
-        let results = calculate2(&lines);
-        results.iter().for_each(|s| println!("Day 6: Start position of Message is {s}"));
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = Vec<String>;
+
+    const PART1: &'static str = "Start position of Packet";
+    const PART2: &'static str = "Start position of Message";
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        let lines = lines::collect(lines)?;
+        validate_signals(&lines)?;
+        Ok(lines)
+    }
+
+    fn part1(&self, lines: &Self::Input) -> Result<Answer> {
+        Ok(calculate1(lines).into())
+    }
+
+    fn part2(&self, lines: &Self::Input) -> Result<Answer> {
+        Ok(calculate2(lines).into())
     }
-    println!()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculate1, calculate2};
+    use super::{calculate1, calculate2, validate_signals};
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
             "bvwbjplbgvbhsrlpgdmjqwftvncz",
             "nppdvjthqldpwncqszvftbrmjlhg",
@@ -74,4 +99,12 @@ mod tests {
         let expected: Vec<usize> = vec![19, 23, 23, 29, 26];
         assert_eq!(results, expected);
     }
+
+    #[test]
+    fn test_invalid_input() {
+        let input = ["abc".to_string(), "abcdé".to_string()];
+        assert_eq!(calculate1(&input[..1]), Vec::<usize>::new());
+        let error = validate_signals(&input).unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(5)));
+    }
 }
\ No newline at end of file
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use crate::lines;
+use crate::error::{Error, Result};
+use crate::lines::{self, LineSource};
+use crate::solution::{Answer, Solution};
 
 #[derive(Debug)]
-enum Item {
+pub enum Item {
     File {
-        _name: String,
+        name: String,
         size: usize,
     },
     Directory {
@@ -13,8 +15,8 @@ enum Item {
     },
 }
 
-#[derive(Debug)]
-struct FileSystem {
+#[derive(Debug, Default)]
+pub struct FileSystem {
     data: HashMap<String, Directory>,
 }
 
@@ -29,63 +31,74 @@ impl FileSystem {
         self.data.keys().collect::<Vec<&String>>()
     }
 
-    fn directory_size(&self, name: &String) -> usize {
+    pub fn get(&self, name: &str) -> Option<&Directory> {
+        self.data.get(name)
+    }
+
+    pub fn directory_size(&self, name: &String) -> Result<usize> {
         let mut size = 0;
-        let dir = self.data.get(name).unwrap();
+        let dir = self.data.get(name)
+            .ok_or_else(|| Error::solve(format!("Directory '{name}' was listed but never visited")))?;
         for item in &dir.files {
             size += match item {
                 Item::File { size, .. } => *size,
-                Item::Directory { name } => self.directory_size(&name),
+                Item::Directory { name } => self.directory_size(name)?,
             }
         }
-        size
+        Ok(size)
     }
 
     fn insert(&mut self, item: Directory) {
         self.data.insert(item.full_name(), item);
     }
 
-    fn remove(&mut self, name: &String) -> Directory {
-        self.data.remove(name).unwrap()
+    fn remove(&mut self, name: &String) -> Option<Directory> {
+        self.data.remove(name)
     }
 
     fn contains_key(&self, name: &String) -> bool {
         self.data.contains_key(name)
     }
 
-    fn size(&self) -> usize {
+    pub fn size(&self) -> Result<usize> {
         self.directory_size(&"".to_string())
     }
-    fn sizes(&self) -> Vec<usize> {
+    pub fn sizes(&self) -> Result<Vec<usize>> {
         self.keys().into_iter()
             .map(|name| self.directory_size(name))
-            .collect::<Vec<usize>>()
+            .collect::<Result<Vec<usize>>>()
     }
 }
 
 #[derive(Debug)]
-struct Directory {
+pub struct Directory {
     name: String,
     parent: Option<String>,
     files: Vec<Item>,
 }
 
 impl Directory {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn files(&self) -> &[Item] {
+        &self.files
+    }
     fn add_item(&mut self, file: Item) {
         self.files.push(file)
     }
-    fn full_name(&self) -> String {
+    pub fn full_name(&self) -> String {
         match &self.parent {
             None => self.name.clone(),
             Some(name) => format!("{}/{}", name, self.name)
         }
     }
-    fn subdirectory_name(&self, name: &String) -> String {
+    fn subdirectory_name(&self, name: &str) -> String {
         format!("{}/{}", self.full_name(), name)
     }
-    fn subdirectory(&self, name: &String) -> Self {
+    fn subdirectory(&self, name: &str) -> Self {
         Directory {
-            name: name.clone(),
+            name: name.to_string(),
             parent: Some(self.full_name()),
             files: vec![],
         }
@@ -93,41 +106,36 @@ impl Directory {
 }
 
 #[derive(Debug)]
-enum Command {
+pub enum Command {
     Cd(CdDirectory),
     Ls,
 }
 
 impl FromStr for Command {
-    type Err = ();
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(" ")
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s.split(' ')
             .collect::<Vec<&str>>();
-        assert!(split.len() >= 2);
-        let first = split.remove(1);
-        match first {
-            "cd" => {
-                let second = split.remove(1);
-                Ok(Command::Cd(second.parse().unwrap()))
-            }
-            "ls" => Ok(Command::Ls),
-            _ => Err(()),
+        match split.as_slice() {
+            ["$", "cd", directory] => Ok(Command::Cd(directory.parse()?)),
+            ["$", "ls"] => Ok(Command::Ls),
+            _ => Err(Error::parse(format!("Unknown command '{s}'"))),
         }
     }
 }
 
 #[derive(Debug)]
-enum CdDirectory {
+pub enum CdDirectory {
     Directory(String),
     Root,
     Parent,
 }
 
 impl FromStr for CdDirectory {
-    type Err = ();
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         match s {
             "/" => Ok(CdDirectory::Root),
             ".." => Ok(CdDirectory::Parent),
@@ -137,76 +145,79 @@ impl FromStr for CdDirectory {
 }
 
 #[derive(Debug)]
-enum LsOutput {
+pub enum LsOutput {
     Directory(String),
     File(String, usize),
 }
 
 impl FromStr for LsOutput {
-    type Err = ();
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(" ")
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s.split(' ')
             .collect::<Vec<&str>>();
-        assert_eq!(split.len(), 2);
-        let first = split.remove(0);
-        let second = split.remove(0);
-        match first {
-            "dir" => Ok(LsOutput::Directory(second.to_string())),
-            _ => Ok(LsOutput::File(second.to_string(), first.parse().unwrap()))
+        match split.as_slice() {
+            ["dir", name] => Ok(LsOutput::Directory(name.to_string())),
+            [size, name] => size.parse()
+                .map(|size| LsOutput::File(name.to_string(), size))
+                .map_err(|e| Error::parse(format!("Invalid file size '{size}': {e}"))),
+            _ => Err(Error::parse(format!("Expected 'dir NAME' or 'SIZE NAME', found '{s}'"))),
         }
     }
 }
 
 #[derive(Debug)]
-enum Output {
+pub enum Output {
     Command(Command),
     Output(LsOutput),
 }
 
 impl FromStr for Output {
-    type Err = ();
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if &s[0..2] == "$ " {
-            Ok(Output::Command(s.parse().unwrap()))
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("$ ") {
+            Ok(Output::Command(s.parse()?))
         } else {
-            Ok(Output::Output(s.parse().unwrap()))
+            Ok(Output::Output(s.parse()?))
         }
     }
 }
 
-fn parse_output(input: &Vec<String>) -> Vec<Output> {
-    input.iter().filter_map(|s| s.parse().ok()).collect()
+pub fn parse_output(input: &[String]) -> Result<Vec<Output>> {
+    input.iter()
+        .enumerate()
+        .map(|(i, s)| s.parse().map_err(|e: Error| e.at_line(i + 1)))
+        .collect()
 }
 
-fn build_root(output: &Output) -> Directory {
+fn build_root(output: &Output) -> Result<Directory> {
     match output {
         Output::Command(c) => {
             match c {
                 Command::Cd(d) => {
                     match d {
-                        CdDirectory::Root => Directory {
+                        CdDirectory::Root => Ok(Directory {
                             name: "".to_string(),
                             parent: None,
                             files: vec![],
-                        },
-                        _ => panic!("First directory must be a /")
+                        }),
+                        _ => Err(Error::parse("First directory must be a /").at_line(1))
                     }
                 }
-                Command::Ls => panic!("First command must be cd")
+                Command::Ls => Err(Error::parse("First command must be cd").at_line(1))
             }
         }
-        Output::Output(_) => panic!("First output must be a command")
+        Output::Output(_) => Err(Error::parse("First output must be a command").at_line(1))
     }
 }
 
-fn build_directories(output: &Vec<Output>) -> FileSystem {
-    assert!(!output.is_empty());
-    let first_line = output.get(0).unwrap();
-    let mut current_directory = build_root(first_line);
+pub fn build_directories(output: &[Output]) -> Result<FileSystem> {
+    let first_line = output.first()
+        .ok_or_else(|| Error::parse("Terminal output is empty"))?;
+    let mut current_directory = build_root(first_line)?;
     let mut file_system = FileSystem::new();
-    for line in &output[1..] {
+    for (i, line) in output.iter().enumerate().skip(1) {
         match line {
             Output::Output(output) => {
                 current_directory.add_item(match output {
@@ -217,7 +228,7 @@ fn build_directories(output: &Vec<Output>) -> FileSystem {
                     }
                     LsOutput::File(name, size) => {
                         Item::File {
-                            _name: name.clone(),
+                            name: name.clone(),
                             size: *size,
                         }
                     }
@@ -229,18 +240,21 @@ fn build_directories(output: &Vec<Output>) -> FileSystem {
                         match name {
                             CdDirectory::Directory(name) => {
                                 let current_name = current_directory.full_name();
-                                assert!(!file_system.contains_key(&current_name));
+                                if file_system.contains_key(&current_name) {
+                                    return Err(Error::parse(format!("Directory '{current_name}' was already visited")).at_line(i + 1));
+                                }
                                 let new_directory = current_directory.subdirectory(name);
                                 file_system.insert(current_directory);
                                 current_directory = new_directory;
                             }
                             CdDirectory::Parent => {
-                                let current_parent = current_directory.parent.clone().expect("Cannot cd to parent from root");
-                                assert!(file_system.contains_key(&current_parent), "current Directories: {:?}, {}", file_system, &current_parent);
+                                let current_parent = current_directory.parent.clone()
+                                    .ok_or_else(|| Error::parse("Cannot cd to parent from root").at_line(i + 1))?;
                                 file_system.insert(current_directory);
                                 current_directory = file_system.remove(&current_parent)
+                                    .ok_or_else(|| Error::parse(format!("Parent directory '{current_parent}' is unknown")).at_line(i + 1))?;
                             }
-                            CdDirectory::Root => panic!("Cannot cd to root anymore"),
+                            CdDirectory::Root => return Err(Error::parse("Cannot cd to root anymore").at_line(i + 1)),
                         }
                     }
                     Command::Ls => {}
@@ -249,38 +263,45 @@ fn build_directories(output: &Vec<Output>) -> FileSystem {
         }
     }
     file_system.insert(current_directory);
-    file_system
+    Ok(file_system)
 }
 
-fn calculate1(file_system: &FileSystem) -> usize {
-    file_system.keys().into_iter()
-        .map(|name| file_system.directory_size(name))
+pub fn calculate1(file_system: &FileSystem) -> Result<usize> {
+    Ok(file_system.sizes()?.into_iter()
         .filter(|s| *s <= 100_000usize)
-        .sum()
+        .sum())
 }
 
-fn calculate2(file_system: &FileSystem) -> usize {
-    let currently_used = file_system.size();
-    let free = 70_000_000 - currently_used;
-    let needed = 30_000_000 - free;
-    file_system.sizes().into_iter()
+pub fn calculate2(file_system: &FileSystem) -> Result<usize> {
+    let currently_used = file_system.size()?;
+    let free = 70_000_000usize.checked_sub(currently_used)
+        .ok_or_else(|| Error::solve(format!("File system uses {currently_used}, more than the disk size")))?;
+    let needed = 30_000_000usize.saturating_sub(free);
+    file_system.sizes()?.into_iter()
         .filter(|s| *s >= needed)
-        .min().unwrap()
+        .min()
+        .ok_or_else(|| Error::solve(format!("No directory frees up {needed}")))
 }
 
-pub(crate) fn main() {
-    let lines = lines::read_lines("resources/day7.txt");
-    if let Ok(lines) = lines {
-        let output = parse_output(&lines);
-        let file_system = build_directories(&output);
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = FileSystem;
 
+    const PART1: &'static str = "Size of directories less than 100_000";
+    const PART2: &'static str = "Minimum size of directory to delete";
 
-        let result = calculate1(&file_system);
-        println!("Day 7: Size of directories less than 100_000 = {result}");
-        let result = calculate2(&file_system);
-        println!("Day 7: Minimum size of directory to delete = {result}");
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input> {
+        build_directories(&parse_output(&lines::collect(lines)?)?)
+    }
+
+    fn part1(&self, file_system: &Self::Input) -> Result<Answer> {
+        calculate1(file_system).map(Answer::from)
+    }
+
+    fn part2(&self, file_system: &Self::Input) -> Result<Answer> {
+        calculate2(file_system).map(Answer::from)
     }
-    println!()
 }
 
 #[cfg(test)]
@@ -288,7 +309,7 @@ mod tests {
     use super::{calculate1, calculate2, parse_output, build_directories};
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "$ cd /",
             "$ ls",
             "dir a",
@@ -318,9 +339,9 @@ mod tests {
     #[test]
     fn test1() {
         let input = test_data();
-        let output = parse_output(&input);
-        let file_system = build_directories(&output);
-        let results = calculate1(&file_system);
+        let output = parse_output(&input).unwrap();
+        let file_system = build_directories(&output).unwrap();
+        let results = calculate1(&file_system).unwrap();
         let expected = 95437usize;
         assert_eq!(results, expected);
     }
@@ -328,10 +349,24 @@ mod tests {
     #[test]
     fn test2() {
         let input = test_data();
-        let output = parse_output(&input);
-        let file_system = build_directories(&output);
-        let results = calculate2(&file_system);
+        let output = parse_output(&input).unwrap();
+        let file_system = build_directories(&output).unwrap();
+        let results = calculate2(&file_system).unwrap();
         let expected = 24933642usize;
         assert_eq!(results, expected);
     }
+
+    #[test]
+    fn test_errors() {
+        let mut input = test_data();
+        input[3] = "14848514b.txt".to_string();
+        let error = parse_output(&input).unwrap_err();
+        assert_eq!(error.line(), Some(4));
+
+        let mut input = test_data();
+        input[16] = "$ cd /".to_string();
+        let output = parse_output(&input).unwrap();
+        let error = build_directories(&output).unwrap_err();
+        assert_eq!(error.line(), Some(17));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The input could not be read at all.
+    Io,
+    /// The input was read but does not have the expected structure.
+    Parse,
+    /// The input was valid but no answer could be computed from it.
+    Solve,
+}
+
+/// Crate wide error, positioned at a one based line and column of an input file where known.
+/// Days can wrap their own error types, which stay reachable through [`Error::downcast_ref`].
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    file: Option<PathBuf>,
+    line: Option<usize>,
+    column: Option<usize>,
+    reason: String,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl Error {
+    pub fn parse<S>(reason: S) -> Self
+        where S: Into<String> {
+        Self::new(ErrorKind::Parse, reason)
+    }
+
+    pub fn solve<S>(reason: S) -> Self
+        where S: Into<String> {
+        Self::new(ErrorKind::Solve, reason)
+    }
+
+    pub fn io(error: io::Error) -> Self {
+        Self::new(ErrorKind::Io, error.to_string())
+    }
+
+    // The message of the typed error becomes the reason
+    pub fn typed<E>(kind: ErrorKind, error: E) -> Self
+        where E: std::error::Error + Send + Sync + 'static {
+        let reason = error.to_string();
+        Self {
+            source: Some(Box::new(error)),
+            ..Self::new(kind, reason)
+        }
+    }
+
+    fn new<S>(kind: ErrorKind, reason: S) -> Self
+        where S: Into<String> {
+        Self {
+            kind,
+            file: None,
+            line: None,
+            column: None,
+            reason: reason.into(),
+            source: None,
+        }
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn in_file<P>(mut self, file: P) -> Self
+        where P: AsRef<Path> {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+        where E: std::error::Error + 'static {
+        self.source.as_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = self.file() {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some(line) = self.line() {
+            write!(f, "{line}:")?;
+        }
+        if let Some(column) = self.column() {
+            write!(f, "{column}:")?;
+        }
+        if self.file.is_some() || self.line.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", self.reason())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+    use super::{Error, ErrorKind};
+
+    #[test]
+    fn test_display() {
+        let error = Error::parse("Unknown hand 'Q'").at(3, 1).in_file("resources/day2.txt");
+        assert_eq!(error.kind(), ErrorKind::Parse);
+        assert_eq!(error.to_string(), "resources/day2.txt:3:1: Unknown hand 'Q'");
+        let error = Error::parse("Missing section").at_line(4);
+        assert_eq!(error.to_string(), "4: Missing section");
+        let error = Error::solve("No directory is big enough");
+        assert_eq!(error.to_string(), "No directory is big enough");
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Empty;
+
+    impl fmt::Display for Empty {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Input is empty")
+        }
+    }
+
+    impl std::error::Error for Empty {}
+
+    #[test]
+    fn test_typed() {
+        let error = Error::typed(ErrorKind::Solve, Empty).at_line(1);
+        assert_eq!(error.downcast_ref::<Empty>(), Some(&Empty));
+        assert_eq!(error.to_string(), "1: Input is empty");
+        assert!(Error::solve("Input is empty").downcast_ref::<Empty>().is_none());
+    }
+}
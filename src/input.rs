@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::lines::{self, LineReader, LineSource};
+
+/// Environment variable naming a directory that holds `dayN.txt` inputs, used instead of `resources/`.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+const DEFAULT_INPUT_DIR: &str = "resources";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    Path(PathBuf),
+    Stdin,
+    Inline(String),
+}
+
+impl InputSource {
+    // Streams the input line by line, without reading it into memory up front
+    pub fn open(&self) -> Result<Box<dyn LineSource + '_>> {
+        Ok(match self {
+            InputSource::Path(path) => Box::new(LineReader::open(path)?),
+            InputSource::Stdin => Box::new(LineReader::new(io::stdin().lock(), self.to_string())),
+            InputSource::Inline(text) => Box::new(LineReader::new(text.as_bytes(), self.to_string())),
+        })
+    }
+
+    pub fn read_lines(&self) -> Result<Vec<String>> {
+        lines::collect(self.open()?.as_mut())
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Path(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin => write!(f, "<stdin>"),
+            InputSource::Inline(_) => write!(f, "<inline>"),
+        }
+    }
+}
+
+/// Decides where the input of each day comes from: an explicit source for that day, then one
+/// for every day, and finally `dayN.txt` in the input directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inputs {
+    dir: PathBuf,
+    all: Option<InputSource>,
+    days: BTreeMap<u8, InputSource>,
+}
+
+impl Inputs {
+    pub fn new<P>(dir: P) -> Self
+        where P: Into<PathBuf> {
+        Self {
+            dir: dir.into(),
+            all: None,
+            days: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(env::var_os(INPUT_DIR_VAR).map_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR), PathBuf::from))
+    }
+
+    // Without a day the source is used for every day that has no source of its own
+    pub fn set(&mut self, day: Option<u8>, source: InputSource) {
+        match day {
+            Some(day) => {
+                self.days.insert(day, source);
+            }
+            None => self.all = Some(source),
+        }
+    }
+
+    pub fn source(&self, day: u8) -> InputSource {
+        self.days.get(&day)
+            .or(self.all.as_ref())
+            .cloned()
+            .unwrap_or_else(|| InputSource::Path(self.dir.join(format!("day{day}.txt"))))
+    }
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self::new(DEFAULT_INPUT_DIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::error::ErrorKind;
+    use super::{InputSource, Inputs};
+
+    #[test]
+    fn test_source() {
+        let mut inputs = Inputs::new("inputs");
+        assert_eq!(inputs.source(3), InputSource::Path(PathBuf::from("inputs/day3.txt")));
+        inputs.set(None, InputSource::Stdin);
+        inputs.set(Some(5), InputSource::Inline("1\n2".to_string()));
+        assert_eq!(inputs.source(3), InputSource::Stdin);
+        assert_eq!(inputs.source(5), InputSource::Inline("1\n2".to_string()));
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let lines = InputSource::Inline("A Y\nB X\n".to_string()).read_lines().unwrap();
+        assert_eq!(lines, vec!["A Y", "B X"]);
+        let error = InputSource::Path(PathBuf::from("missing/day1.txt")).read_lines().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Io);
+        assert!(error.to_string().starts_with("missing/day1.txt:"));
+    }
+}
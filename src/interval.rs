@@ -0,0 +1,452 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+pub fn fully_contains<T>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool
+    where T: PartialOrd {
+    r1.contains(r2.start()) && r1.contains(r2.end()) || r2.contains(r1.start()) && r2.contains(r1.end())
+}
+
+pub fn partly_contains<T>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool
+    where T: PartialOrd {
+    r1.contains(r2.start()) || r1.contains(r2.end()) || r2.contains(r1.start()) || r2.contains(r1.end())
+}
+
+/// Integers that intervals can be built from. Every value has an index, so that neighbours,
+/// lengths and adjacency can be computed without overflowing `T` itself.
+pub trait Discrete: Copy + Ord {
+    const MIN: Self;
+    const MAX: Self;
+
+    fn index(self) -> i128;
+    // Only called with indices of values of `T`
+    fn from_index(index: i128) -> Self;
+}
+
+macro_rules! discrete {
+    ($($t:ty),*) => {
+        $(impl Discrete for $t {
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn index(self) -> i128 {
+                self as i128
+            }
+
+            fn from_index(index: i128) -> Self {
+                index as $t
+            }
+        })*
+    };
+}
+
+discrete!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// An inclusive interval of integers, empty when `start > end` just like [`RangeInclusive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T> Interval<T>
+    where T: Discrete {
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    // Number of values, which does not fit into `T` for e.g. the whole of `u64`
+    pub fn len(&self) -> u128 {
+        if self.is_empty() {
+            0
+        } else {
+            (self.end.index() - self.start.index() + 1) as u128
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    // Whether `other` lies within this interval; the empty interval lies within every one.
+    // Unlike `fully_contains` this is not symmetric
+    pub fn covers(&self, other: &Interval<T>) -> bool {
+        other.is_empty() || self.contains(other.start) && self.contains(other.end)
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        !self.is_empty() && !other.is_empty() && partly_contains(&self.range(), &other.range())
+    }
+
+    // Whether the intervals do not overlap but leave no value between each other
+    pub fn is_adjacent(&self, other: &Interval<T>) -> bool {
+        !self.is_empty() && !other.is_empty()
+            && (self.end.index() + 1 == other.start.index() || other.end.index() + 1 == self.start.index())
+    }
+
+    pub fn intersection(&self, other: &Interval<T>) -> Interval<T> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // The smallest interval holding the values of both, unless some value between them would
+    // belong to neither
+    pub fn union(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        if other.is_empty() {
+            Some(*self)
+        } else if self.is_empty() {
+            Some(*other)
+        } else if self.overlaps(other) || self.is_adjacent(other) {
+            Some(Interval::new(self.start.min(other.start), self.end.max(other.end)))
+        } else {
+            None
+        }
+    }
+
+    // The values of this interval that are not in `other`, as up to two intervals in order
+    pub fn difference(&self, other: &Interval<T>) -> Vec<Interval<T>> {
+        if !self.overlaps(other) {
+            return if self.is_empty() { vec![] } else { vec![*self] };
+        }
+        let mut pieces = vec![];
+        if self.start < other.start {
+            pieces.push(Interval::new(self.start, T::from_index(other.start.index() - 1)));
+        }
+        if other.end < self.end {
+            pieces.push(Interval::new(T::from_index(other.end.index() + 1), self.end));
+        }
+        pieces
+    }
+
+    pub fn range(&self) -> RangeInclusive<T> {
+        self.start..=self.end
+    }
+}
+
+impl<T> From<RangeInclusive<T>> for Interval<T>
+    where T: Discrete {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (start, end) = range.into_inner();
+        Interval::new(start, end)
+    }
+}
+
+impl<T> From<Interval<T>> for RangeInclusive<T>
+    where T: Discrete {
+    fn from(interval: Interval<T>) -> Self {
+        interval.range()
+    }
+}
+
+impl<T> fmt::Display for Interval<T>
+    where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Disjoint, sorted intervals. Overlapping and adjacent intervals are merged as they are
+/// inserted, so every set of values has exactly one representation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { intervals: vec![] }
+    }
+}
+
+impl<T> IntervalSet<T>
+    where T: Discrete {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // Number of values in the set
+    pub fn covered(&self) -> u128 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.intervals.partition_point(|interval| interval.end < value);
+        self.intervals.get(i).is_some_and(|interval| interval.contains(value))
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        // the intervals from `first` up to `last` overlap or touch the new one
+        let first = self.intervals.partition_point(|other| other.end.index() + 1 < interval.start.index());
+        let last = self.intervals.partition_point(|other| other.start.index() <= interval.end.index() + 1);
+        let merged = self.intervals[first..last].iter()
+            .fold(interval, |merged, other| merged.union(other).expect("Only touching intervals are merged"));
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    pub fn remove(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let first = self.intervals.partition_point(|other| other.end < interval.start);
+        let last = self.intervals.partition_point(|other| other.start <= interval.end);
+        let rest = self.intervals[first..last].iter()
+            .flat_map(|other| other.difference(&interval))
+            .collect::<Vec<Interval<T>>>();
+        self.intervals.splice(first..last, rest);
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+        union.extend(other.intervals.iter().copied());
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intersection = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intersection.insert(a.intersection(b));
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        intersection
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut difference = self.clone();
+        for interval in &other.intervals {
+            difference.remove(*interval);
+        }
+        difference
+    }
+
+    // The values between the first and the last value of the set that are not in it
+    pub fn gaps(&self) -> IntervalSet<T> {
+        let intervals = self.intervals.windows(2)
+            .map(|pair| Interval::new(T::from_index(pair[0].end.index() + 1), T::from_index(pair[1].start.index() - 1)))
+            .collect();
+        IntervalSet { intervals }
+    }
+}
+
+impl<T> Extend<Interval<T>> for IntervalSet<T>
+    where T: Discrete {
+    fn extend<I>(&mut self, iter: I)
+        where I: IntoIterator<Item=Interval<T>> {
+        for interval in iter {
+            self.insert(interval);
+        }
+    }
+}
+
+impl<T> FromIterator<Interval<T>> for IntervalSet<T>
+    where T: Discrete {
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item=Interval<T>> {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Intervals with values, stored for overlap queries in O(log n + k) for k results. The
+/// entries are sorted by start and form an implicit balanced tree whose node at the middle of
+/// every slice knows the largest end within that slice.
+#[derive(Debug, Clone)]
+pub struct IntervalTree<T, V> {
+    entries: Vec<(Interval<T>, V)>,
+    max_end: Vec<T>,
+    covered: IntervalSet<T>,
+}
+
+impl<T, V> IntervalTree<T, V>
+    where T: Discrete {
+    // Empty intervals overlap nothing and are dropped
+    pub fn new<I>(entries: I) -> Self
+        where I: IntoIterator<Item=(Interval<T>, V)> {
+        let mut entries = entries.into_iter()
+            .filter(|(interval, _)| !interval.is_empty())
+            .collect::<Vec<(Interval<T>, V)>>();
+        entries.sort_by_key(|(interval, _)| (interval.start, interval.end));
+        let mut max_end = entries.iter().map(|(interval, _)| interval.end).collect::<Vec<T>>();
+        Self::build(&mut max_end, 0, entries.len());
+        let covered = entries.iter().map(|(interval, _)| *interval).collect();
+        Self { entries, max_end, covered }
+    }
+
+    // Fills in the largest end of the slice `lo..hi` at its middle and returns it
+    fn build(max_end: &mut [T], lo: usize, hi: usize) -> Option<T> {
+        if lo == hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::build(max_end, lo, mid);
+        let right = Self::build(max_end, mid + 1, hi);
+        max_end[mid] = [left, right].into_iter().flatten().fold(max_end[mid], T::max);
+        Some(max_end[mid])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[(Interval<T>, V)] {
+        &self.entries
+    }
+
+    // Entries overlapping `interval`, ordered by their start
+    pub fn overlapping(&self, interval: Interval<T>) -> Vec<&(Interval<T>, V)> {
+        let mut found = vec![];
+        if !interval.is_empty() {
+            self.search(interval, 0, self.entries.len(), &mut found);
+        }
+        found
+    }
+
+    fn search<'a>(&'a self, interval: Interval<T>, lo: usize, hi: usize, found: &mut Vec<&'a (Interval<T>, V)>) {
+        if lo == hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        // nothing in this slice reaches the interval
+        if self.max_end[mid] < interval.start {
+            return;
+        }
+        self.search(interval, lo, mid, found);
+        let entry = &self.entries[mid];
+        // everything from here on starts after the interval
+        if entry.0.start > interval.end {
+            return;
+        }
+        if entry.0.overlaps(&interval) {
+            found.push(entry);
+        }
+        self.search(interval, mid + 1, hi, found);
+    }
+
+    pub fn containing(&self, value: T) -> Vec<&(Interval<T>, V)> {
+        self.overlapping(Interval::new(value, value))
+    }
+
+    // The values in at least one interval
+    pub fn covered(&self) -> &IntervalSet<T> {
+        &self.covered
+    }
+
+    pub fn uncovered(&self, within: Interval<T>) -> IntervalSet<T> {
+        IntervalSet::from_iter([within]).difference(&self.covered)
+    }
+}
+
+impl<T, V> FromIterator<(Interval<T>, V)> for IntervalTree<T, V>
+    where T: Discrete {
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item=(Interval<T>, V)> {
+        Self::new(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interval, IntervalSet, IntervalTree};
+
+    #[test]
+    fn test_interval() {
+        let a = Interval::from(2u32..=6);
+        let b = Interval::new(5, 9);
+        assert_eq!((a.len(), a.is_empty()), (5, false));
+        assert!(a.overlaps(&b) && !a.covers(&b) && a.covers(&Interval::new(3, 4)));
+        assert_eq!(a.intersection(&b), Interval::new(5, 6));
+        assert!(a.intersection(&Interval::new(7, 9)).is_empty());
+        assert_eq!(a.union(&b), Some(Interval::new(2, 9)));
+        assert!(a.is_adjacent(&Interval::new(7, 9)));
+        assert_eq!(a.union(&Interval::new(7, 9)), Some(Interval::new(2, 9)));
+        assert_eq!(a.union(&Interval::new(8, 9)), None);
+        assert_eq!(a.difference(&b), vec![Interval::new(2, 4)]);
+        assert_eq!(a.difference(&Interval::new(3, 4)), vec![Interval::new(2, 2), Interval::new(5, 6)]);
+        assert_eq!(a.difference(&Interval::new(0, 9)), vec![]);
+        assert_eq!(Interval::new(u64::MIN, u64::MAX).len(), 1 << 64);
+        assert_eq!(Interval::new(i8::MIN, i8::MAX).difference(&Interval::new(i8::MIN, 0)), vec![Interval::new(1, i8::MAX)]);
+        assert_eq!(a.to_string(), "2-6");
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let mut set = [(10, 12), (1, 3), (5, 6), (4, 4), (20, 25)].into_iter()
+            .map(|(start, end)| Interval::new(start, end))
+            .collect::<IntervalSet<i32>>();
+        assert_eq!(set.intervals(), &[Interval::new(1, 6), Interval::new(10, 12), Interval::new(20, 25)]);
+        assert_eq!(set.covered(), 15);
+        assert!(set.contains(11) && !set.contains(7) && !set.contains(26));
+        assert_eq!(set.gaps().intervals(), &[Interval::new(7, 9), Interval::new(13, 19)]);
+
+        set.insert(Interval::new(11, 21));
+        assert_eq!(set.intervals(), &[Interval::new(1, 6), Interval::new(10, 25)]);
+        set.remove(Interval::new(3, 12));
+        assert_eq!(set.intervals(), &[Interval::new(1, 2), Interval::new(13, 25)]);
+
+        let other = IntervalSet::from_iter([Interval::new(0, 1), Interval::new(20, 30)]);
+        assert_eq!(set.intersection(&other).intervals(), &[Interval::new(1, 1), Interval::new(20, 25)]);
+        assert_eq!(set.union(&other).intervals(), &[Interval::new(0, 2), Interval::new(13, 30)]);
+        assert_eq!(set.difference(&other).intervals(), &[Interval::new(2, 2), Interval::new(13, 19)]);
+    }
+
+    #[test]
+    fn test_interval_tree() {
+        let intervals = [(5, 8), (1, 3), (2, 9), (12, 15), (7, 7), (4, 2)];
+        let tree = intervals.iter()
+            .enumerate()
+            .map(|(i, (start, end))| (Interval::new(*start, *end), i))
+            .collect::<IntervalTree<u32, usize>>();
+        assert_eq!(tree.len(), 5);
+        let values = |found: Vec<&(Interval<u32>, usize)>| found.iter().map(|(_, i)| *i).collect::<Vec<usize>>();
+        assert_eq!(values(tree.containing(7)), vec![2, 0, 4]);
+        assert_eq!(values(tree.containing(10)), vec![]);
+        assert_eq!(values(tree.overlapping(Interval::new(9, 12))), vec![2, 3]);
+        assert_eq!(values(tree.overlapping(Interval::new(3, 2))), vec![]);
+        assert_eq!(tree.uncovered(Interval::new(0, 16)).intervals(), &[Interval::new(0, 0), Interval::new(10, 11), Interval::new(16, 16)]);
+
+        // every query agrees with a linear scan
+        let tree = (0..200u32)
+            .map(|i| (Interval::new(i * 37 % 101, i * 37 % 101 + i % 13), i))
+            .collect::<IntervalTree<u32, u32>>();
+        for start in 0..120 {
+            let query = Interval::new(start, start + start % 5);
+            let mut expected = tree.entries().iter()
+                .filter(|(interval, _)| interval.overlaps(&query))
+                .collect::<Vec<&(Interval<u32>, u32)>>();
+            expected.sort_by_key(|(interval, i)| (interval.start(), interval.end(), *i));
+            let mut found = tree.overlapping(query);
+            found.sort_by_key(|(interval, i)| (interval.start(), interval.end(), *i));
+            assert_eq!(found, expected);
+        }
+    }
+}
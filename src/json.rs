@@ -0,0 +1,119 @@
+use std::fmt;
+
+use crate::solution::Answer;
+
+/// Minimal JSON value, enough to emit machine readable reports without extra dependencies.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Integer(u64),
+    // e.g. the negative sections of day 4
+    Signed(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<K, I>(fields: I) -> Self
+        where K: Into<String>, I: IntoIterator<Item=(K, Json)> {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+}
+
+impl From<&Answer> for Json {
+    fn from(answer: &Answer) -> Self {
+        match answer {
+            Answer::Number(n) => Json::Integer(*n),
+            Answer::Text(s) => Json::String(s.clone()),
+            Answer::List(items) => Json::Array(items.iter().map(Json::from).collect()),
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl<T> From<Option<T>> for Json
+    where T: Into<Json> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, T::into)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Integer(n) => write!(f, "{n}"),
+            Json::Signed(n) => write!(f, "{n}"),
+            Json::Float(n) if n.is_finite() => write!(f, "{n}"),
+            Json::Float(_) => write!(f, "null"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solution::Answer;
+    use super::Json;
+
+    #[test]
+    fn test_display() {
+        let answer = Answer::List(vec![Answer::Number(7), Answer::Text("C\"M\nZ".to_string())]);
+        let json = Json::object([
+            ("day", Json::Integer(5)),
+            ("answer", Json::from(&answer)),
+            ("error", Json::from(None::<&str>)),
+            ("ok", Json::Bool(true)),
+        ]);
+        assert_eq!(json.to_string(), r#"{"day":5,"answer":[7,"C\"M\nZ"],"error":null,"ok":true}"#);
+        assert_eq!(Json::Float(0.5).to_string(), "0.5");
+        assert_eq!(Json::Signed(-3).to_string(), "-3");
+        assert_eq!(Json::String("\u{1}".to_string()).to_string(), r#""\u0001""#);
+    }
+}
@@ -0,0 +1,22 @@
+//! Advent of Code solutions, usable as a library: every day exposes its domain types and solvers,
+//! and the [`solution::Registry`] ties them together for front ends such as the `aoc23` binary.
+
+pub mod answers;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod error;
+pub mod input;
+pub mod interval;
+pub mod json;
+pub mod lines;
+pub mod pool;
+pub mod runner;
+pub mod solution;
+pub mod stats;
+pub mod timing;
+pub mod toml;
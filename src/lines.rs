@@ -1,16 +1,277 @@
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-// The output is wrapped in a Result to allow matching on errors
-// Returns an Iterator to the Reader of the lines of the file.
-pub(crate) fn read_lines<P>(filename: P) -> io::Result<Vec<String>>
+use crate::error::{Error, Result};
+
+/// A stream of input lines without their line endings. Every line is borrowed until the next
+/// call, so readers can reuse one buffer and inputs never have to fit into memory.
+pub trait LineSource {
+    fn next_line(&mut self) -> Option<Result<&str>>;
+}
+
+/// Reads lines from any reader into a single reused buffer.
+pub struct LineReader<R> {
+    reader: R,
+    name: PathBuf,
+    buffer: String,
+    line: usize,
+}
+
+impl<R> LineReader<R>
+    where R: BufRead {
+    // `name` positions errors, e.g. the file name or "<stdin>"
+    pub fn new<P>(reader: R, name: P) -> Self
+        where P: AsRef<Path> {
+        Self {
+            reader,
+            name: name.as_ref().to_path_buf(),
+            buffer: String::new(),
+            line: 0,
+        }
+    }
+}
+
+impl LineReader<io::BufReader<File>> {
+    pub fn open<P>(filename: P) -> Result<Self>
+        where P: AsRef<Path> {
+        let filename = filename.as_ref();
+        let file = File::open(filename).map_err(|e| Error::io(e).in_file(filename))?;
+        Ok(Self::new(io::BufReader::new(file), filename))
+    }
+}
+
+impl<R> LineSource for LineReader<R>
+    where R: BufRead {
+    fn next_line(&mut self) -> Option<Result<&str>> {
+        self.buffer.clear();
+        self.line += 1;
+        match self.reader.read_line(&mut self.buffer) {
+            Ok(0) => None,
+            Ok(_) => {
+                // same line endings as BufRead::lines
+                if self.buffer.ends_with('\n') {
+                    self.buffer.pop();
+                    if self.buffer.ends_with('\r') {
+                        self.buffer.pop();
+                    }
+                }
+                Some(Ok(&self.buffer))
+            }
+            Err(e) => {
+                let error = match e.kind() {
+                    io::ErrorKind::InvalidData => Error::parse(e.to_string()),
+                    _ => Error::io(e),
+                };
+                Some(Err(error.at_line(self.line).in_file(&self.name)))
+            }
+        }
+    }
+}
+
+/// Lines that are already in memory, e.g. the sample data of the tests.
+pub struct SliceLines<'a> {
+    lines: std::slice::Iter<'a, String>,
+}
+
+impl<'a> SliceLines<'a> {
+    pub fn new(lines: &'a [String]) -> Self {
+        Self {
+            lines: lines.iter(),
+        }
+    }
+}
+
+impl LineSource for SliceLines<'_> {
+    fn next_line(&mut self) -> Option<Result<&str>> {
+        self.lines.next().map(|line| Ok(line.as_str()))
+    }
+}
+
+// Calls `f` with the one based number and the content of every line, stopping at the first error.
+pub fn try_for_each<F>(lines: &mut dyn LineSource, mut f: F) -> Result<()>
+    where F: FnMut(usize, &str) -> Result<()> {
+    let mut i = 0;
+    while let Some(line) = lines.next_line() {
+        i += 1;
+        f(i, line?)?;
+    }
+    Ok(())
+}
+
+// For days that need their whole input at once.
+pub fn collect(lines: &mut dyn LineSource) -> Result<Vec<String>> {
+    let mut collected = vec![];
+    try_for_each(lines, |_, line| {
+        collected.push(line.to_string());
+        Ok(())
+    })?;
+    Ok(collected)
+}
+
+// Lines with nothing but whitespace, including a stray '\r', separate sections like empty ones
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// A run of non blank lines, e.g. the items of one elf or the crate drawing of day5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// One based line number of the first line in the input.
+    pub first_line: usize,
+    pub lines: &'a [String],
+}
+
+impl<'a> Section<'a> {
+    pub fn last_line(&self) -> usize {
+        self.first_line + self.lines.len() - 1
+    }
+
+    // Lines with their one based numbers, without a trailing '\r' left by CRLF endings
+    pub fn numbered(&self) -> impl Iterator<Item=(usize, &'a str)> + '_ {
+        self.lines.iter()
+            .enumerate()
+            .map(|(i, line)| (self.first_line + i, line.strip_suffix('\r').unwrap_or(line)))
+    }
+}
+
+// Splits the lines at every run of blank lines; leading and trailing blank lines are ignored
+pub fn sections(lines: &[String]) -> Vec<Section<'_>> {
+    let mut sections = vec![];
+    let mut start = None;
+    for (i, line) in lines.iter().enumerate() {
+        match (start, is_blank(line)) {
+            (None, false) => start = Some(i),
+            (Some(first), true) => {
+                sections.push(Section { first_line: first + 1, lines: &lines[first..i] });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        sections.push(Section { first_line: first + 1, lines: &lines[first..] });
+    }
+    sections
+}
+
+// Like `sections`, but fails at the first line of a section beyond `count`, or at the last
+// line if the input ends before `count` sections were found.
+pub fn expect_sections(lines: &[String], count: usize) -> Result<Vec<Section<'_>>> {
+    let sections = sections(lines);
+    if let Some(extra) = sections.get(count) {
+        return Err(Error::parse(format!("Expected {count} sections separated by blank lines, found another one"))
+            .at_line(extra.first_line));
+    }
+    if sections.len() < count {
+        return Err(Error::parse(format!("Expected {count} sections separated by blank lines, found {}", sections.len()))
+            .at_line(lines.len().max(1)));
+    }
+    Ok(sections)
+}
+
+/// What [`try_for_each_paragraph`] sees of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paragraph<'a> {
+    /// A non blank line with its one based number, without a trailing '\r'.
+    Line(usize, &'a str),
+    /// The end of a section, after its last line.
+    End,
+}
+
+// Streaming form of `sections`, calling `f` for every line of a section and at its end.
+// Returns the number of sections.
+pub fn try_for_each_paragraph<F>(lines: &mut dyn LineSource, mut f: F) -> Result<usize>
+    where F: FnMut(Paragraph<'_>) -> Result<()> {
+    let mut count = 0;
+    let mut open = false;
+    try_for_each(lines, |i, line| {
+        match (open, is_blank(line)) {
+            (_, false) => {
+                count += usize::from(!open);
+                open = true;
+                f(Paragraph::Line(i, line.strip_suffix('\r').unwrap_or(line)))
+            }
+            (true, true) => {
+                open = false;
+                f(Paragraph::End)
+            }
+            (false, true) => Ok(()),
+        }
+    })?;
+    if open {
+        f(Paragraph::End)?;
+    }
+    Ok(count)
+}
+
+// Reads all lines of the file, failing on the first line that cannot be read
+// instead of silently dropping it.
+pub fn read_lines<P>(filename: P) -> Result<Vec<String>>
     where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    let lines = io::BufReader::new(file).lines();
-    let lines = lines.into_iter()
-        .filter_map(|e| e.ok())
-        .collect();
-    Ok(lines)
+    collect(&mut LineReader::open(filename)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use super::{collect, expect_sections, sections, try_for_each, try_for_each_paragraph, LineReader, LineSource, Paragraph, SliceLines};
+
+    #[test]
+    fn test_line_reader() {
+        let mut reader = LineReader::new("a\r\nb\n\nc".as_bytes(), "<test>");
+        assert_eq!(reader.next_line().unwrap().unwrap(), "a");
+        assert_eq!(collect(&mut reader).unwrap(), vec!["b", "", "c"]);
+        assert!(reader.next_line().is_none());
+
+        let mut reader = LineReader::new(&b"ok\n\xff\n"[..], "<test>");
+        let error = collect(&mut reader).unwrap_err();
+        assert_eq!((error.line(), error.file()), (Some(2), Some(Path::new("<test>"))));
+    }
+
+    #[test]
+    fn test_try_for_each() {
+        let lines = ["1", "2", "3"].map(str::to_string);
+        let mut numbers = vec![];
+        try_for_each(&mut SliceLines::new(&lines), |i, line| {
+            numbers.push((i, line.parse::<u32>().unwrap()));
+            Ok(())
+        }).unwrap();
+        assert_eq!(numbers, vec![(1, 1), (2, 2), (3, 3)]);
+    }
+
+    fn paragraphs() -> Vec<String> {
+        ["", "a", "b\r", " \t", "\r", "c", "", ""].map(str::to_string).to_vec()
+    }
+
+    #[test]
+    fn test_sections() {
+        let lines = paragraphs();
+        let sections = sections(&lines);
+        assert_eq!(sections.len(), 2);
+        assert_eq!((sections[0].first_line, sections[0].last_line()), (2, 3));
+        assert_eq!(sections[0].numbered().collect::<Vec<(usize, &str)>>(), vec![(2, "a"), (3, "b")]);
+        assert_eq!(sections[1].lines, &["c".to_string()]);
+        assert!(expect_sections(&lines, 2).is_ok());
+        let error = expect_sections(&lines, 1).unwrap_err();
+        assert_eq!(error.line(), Some(6));
+        let error = expect_sections(&lines, 3).unwrap_err();
+        assert_eq!(error.line(), Some(8));
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let lines = paragraphs();
+        let mut seen = vec![];
+        let count = try_for_each_paragraph(&mut SliceLines::new(&lines[..6]), |paragraph| {
+            seen.push(match paragraph {
+                Paragraph::Line(i, line) => format!("{i}:{line}"),
+                Paragraph::End => "end".to_string(),
+            });
+            Ok(())
+        }).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(seen, vec!["2:a", "3:b", "end", "6:c", "end"]);
+    }
 }
@@ -1,18 +1,275 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod lines;
-
-fn main() {
-    day1::main();
-    day2::main();
-    day3::main();
-    day4::main();
-    day5::main();
-    day6::main();
-    day7::main();
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+
+use aoc23::answers::{Answers, Verdict};
+use aoc23::day1::Inventory;
+use aoc23::day2::{Day2, Rules};
+use aoc23::day3::plan_reorganization;
+use aoc23::day4::{parse_groups, Camp};
+use aoc23::error::{Error, ErrorKind};
+use aoc23::input::{InputSource, Inputs};
+use aoc23::runner::DayReport;
+use aoc23::solution::{Puzzle, Registry};
+use aoc23::{pool, runner, timing};
+
+use crate::cli::{Args, Failure, Mode};
+use crate::output::{PartResult, Reporter};
+
+mod cli;
+mod coverage;
+mod output;
+mod plan;
+mod report;
+mod tournament;
+
+fn failure(day: u8, source: &InputSource, e: Error) -> Failure {
+    match e.kind() {
+        ErrorKind::Io => Failure::InputMissing(day, e),
+        _ if e.line().is_some() && e.file().is_none() => Failure::SolverFailed(day, e.in_file(source.to_string())),
+        _ => Failure::SolverFailed(day, e),
+    }
+}
+
+fn catch_panic<T, F>(f: F) -> Result<T, Error>
+    where F: FnOnce() -> Result<T, Error> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(Error::solve("Solver panicked")))
+}
+
+// Reports every requested part of a day that failed as a whole, e.g. because its input is missing
+fn report_failure(reporter: &mut Reporter, puzzle: &dyn Puzzle, day: u8, args: &Args, failure: &Failure) {
+    for part in &args.parts {
+        reporter.part(PartResult {
+            day,
+            part: *part,
+            title: puzzle.title(*part),
+            answer: Err(failure.to_string()),
+            duration: None,
+            verdict: None,
+            recorded: false,
+        });
+    }
+}
+
+// Reads and solves a day without reporting anything, so it can run on a worker thread
+fn solve_day(registry: &Registry, day: u8, source: &InputSource, args: &Args) -> Result<DayReport, Failure> {
+    let puzzle = registry.get(day).ok_or(Failure::UnknownDay(day))?;
+    let solve = if args.stream { runner::stream } else { runner::run };
+    source.open()
+        .and_then(|mut lines| catch_panic(|| solve(day, puzzle, lines.as_mut(), &args.parts, args.jobs > 1)))
+        .map_err(|e| failure(day, source, e))
+}
+
+fn report_day(registry: &Registry, day: u8, source: &InputSource, report: Result<DayReport, Failure>, args: &Args, answers: &mut Answers, reporter: &mut Reporter) -> Result<(), Failure> {
+    let puzzle = registry.get(day).ok_or(Failure::UnknownDay(day))?;
+    let report = report.inspect_err(|failure| report_failure(reporter, puzzle, day, args, failure))?;
+    reporter.parsed(day, report.parse);
+    let mut result = Ok(());
+    for part in report.parts {
+        let answer = part.answer.map_err(|e| failure(day, source, e));
+        let mut verdict = None;
+        let mut recorded = false;
+        if let (Ok(answer), true) = (&answer, args.check || args.record) {
+            let checked = answers.check(day, part.part, answer);
+            match checked {
+                Verdict::Wrong(_) => result = result.and(Err(Failure::WrongAnswer(day, part.part))),
+                Verdict::Unknown if args.record => {
+                    answers.record(day, part.part, answer.clone());
+                    recorded = true;
+                }
+                _ => {}
+            }
+            verdict = Some(checked);
+        }
+        reporter.part(PartResult {
+            day,
+            part: part.part,
+            title: part.title,
+            answer: answer.as_ref().map_err(Failure::to_string),
+            duration: Some(part.duration),
+            verdict,
+            recorded,
+        });
+        if let Err(failure) = answer {
+            result = result.and(Err(failure));
+        }
+    }
+    reporter.day_done();
+    result
+}
+
+fn bench_day(registry: &Registry, day: u8, source: &InputSource, args: &Args, reporter: &mut Reporter) -> Result<(), Failure> {
+    let puzzle = registry.get(day).ok_or(Failure::UnknownDay(day))?;
+    let bench = source.read_lines()
+        .and_then(|lines| catch_panic(|| timing::bench(puzzle, &lines, &args.parts, args.runs)))
+        .map_err(|e| failure(day, source, e))?;
+    reporter.samples(day, "parse", &bench.parse);
+    for (part, samples) in &bench.parts {
+        reporter.samples(day, &part.to_string(), samples);
+    }
+    reporter.day_done();
+    Ok(())
+}
+
+fn report_inventory(source: &InputSource, args: &Args) -> Result<(), Failure> {
+    let inventory = source.open()
+        .and_then(|mut lines| Inventory::read(lines.as_mut()))
+        .map_err(|e| failure(1, source, e))?;
+    report::print(&inventory, args);
+    Ok(())
+}
+
+fn report_coverage(source: &InputSource, args: &Args) -> Result<(), Failure> {
+    // groups of any size, with negative and open ranges, unlike the puzzle itself
+    let groups = source.read_lines()
+        .and_then(|lines| parse_groups(&lines))
+        .map_err(|e| failure(4, source, e))?;
+    coverage::print(&Camp::from_groups(&groups), args);
+    Ok(())
+}
+
+fn report_plan(source: &InputSource, args: &Args) -> Result<(), Failure> {
+    let layouts = source.read_lines()
+        .and_then(|lines| plan_reorganization(&lines))
+        .map_err(|e| failure(3, source, e))?;
+    plan::print(&layouts, args);
+    Ok(())
+}
+
+fn report_tournament(rules: &Rules, source: &InputSource, args: &Args) -> Result<(), Failure> {
+    source.read_lines()
+        .and_then(|lines| tournament::print(rules, &lines, args))
+        .map_err(|e| failure(2, source, e))
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(failure) => {
+            eprintln!("{failure}");
+            return failure.exit_code();
+        }
+    };
+    let rules = match args.rules.as_ref().map(Rules::load).transpose() {
+        Ok(rules) => rules.unwrap_or_default(),
+        Err(e) => {
+            let failure = Failure::RulesFile(e);
+            eprintln!("{failure}");
+            return failure.exit_code();
+        }
+    };
+    let mut registry = Registry::default();
+    registry.register(2, Day2::with_rules(rules.clone()));
+    let days = if args.mode == Mode::Report {
+        vec![1]
+    } else if args.mode == Mode::Tournament {
+        vec![2]
+    } else if args.mode == Mode::Coverage {
+        vec![4]
+    } else if args.mode == Mode::Plan {
+        vec![3]
+    } else if args.days.is_empty() {
+        registry.days().collect()
+    } else {
+        args.days.clone()
+    };
+    if let Some(day) = days.iter().find(|day| registry.get(**day).is_none()) {
+        let failure = Failure::UnknownDay(*day);
+        eprintln!("{failure}");
+        return failure.exit_code();
+    }
+    let mut inputs = Inputs::from_env();
+    for (day, source) in &args.inputs {
+        inputs.set(*day, source.clone());
+    }
+    if days.iter().filter(|day| inputs.source(**day) == InputSource::Stdin).count() > 1 {
+        let failure = Failure::Usage("Only one day can read its input from stdin".to_string());
+        eprintln!("{failure}");
+        return failure.exit_code();
+    }
+    let mut answers = if args.check || args.record {
+        match Answers::load(&args.answers) {
+            Ok(answers) => answers,
+            Err(e) => {
+                let failure = Failure::AnswersFile(e);
+                eprintln!("{failure}");
+                return failure.exit_code();
+            }
+        }
+    } else {
+        Answers::default()
+    };
+    let recorded = answers.clone();
+    let mut reporter = Reporter::new(args.format, args.time);
+    let mut first_failure = None;
+    let mut fail = |failure: Failure| {
+        eprintln!("{failure}");
+        first_failure.get_or_insert(failure);
+    };
+    let jobs = days.iter()
+        .map(|day| (*day, inputs.source(*day)))
+        .collect::<Vec<(u8, InputSource)>>();
+    match args.mode {
+        // days are solved on the pool but reported in order on this thread
+        Mode::Run => pool::run_ordered(
+            &jobs,
+            args.jobs,
+            |(day, source)| solve_day(&registry, *day, source, &args),
+            |(day, source), report| {
+                if let Err(failure) = report_day(&registry, *day, source, report, &args, &mut answers, &mut reporter) {
+                    fail(failure);
+                }
+            },
+        ),
+        Mode::Bench => {
+            for (day, source) in &jobs {
+                if let Err(failure) = bench_day(&registry, *day, source, &args, &mut reporter) {
+                    fail(failure);
+                }
+            }
+        }
+        Mode::Report => {
+            for (_, source) in &jobs {
+                if let Err(failure) = report_inventory(source, &args) {
+                    fail(failure);
+                }
+            }
+        }
+        Mode::Tournament => {
+            for (_, source) in &jobs {
+                if let Err(failure) = report_tournament(&rules, source, &args) {
+                    fail(failure);
+                }
+            }
+        }
+        Mode::Coverage => {
+            for (_, source) in &jobs {
+                if let Err(failure) = report_coverage(source, &args) {
+                    fail(failure);
+                }
+            }
+        }
+        Mode::Plan => {
+            for (_, source) in &jobs {
+                if let Err(failure) = report_plan(source, &args) {
+                    fail(failure);
+                }
+            }
+        }
+    }
+    // the other modes print their own output
+    if matches!(args.mode, Mode::Run | Mode::Bench) {
+        reporter.finish();
+    }
+    if answers != recorded {
+        if let Err(e) = answers.save(&args.answers) {
+            let failure = Failure::AnswersFile(e);
+            eprintln!("{failure}");
+            first_failure.get_or_insert(failure);
+        }
+    }
+    match first_failure {
+        None => ExitCode::SUCCESS,
+        Some(failure) => failure.exit_code(),
+    }
 }
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use aoc23::answers::Verdict;
+use aoc23::json::Json;
+use aoc23::solution::{Answer, Part};
+use aoc23::timing::Samples;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Format {
+    Text,
+    Json,
+    Ndjson,
+}
+
+fn nanos(duration: Duration) -> Json {
+    Json::Integer(duration.as_nanos().try_into().unwrap_or(u64::MAX))
+}
+
+fn verdict_json(verdict: &Verdict) -> Json {
+    let (status, expected) = match verdict {
+        Verdict::Correct => ("correct", None),
+        Verdict::Wrong(expected) => ("wrong", Some(expected)),
+        Verdict::Unknown => ("unknown", None),
+    };
+    Json::object([
+        ("status", Json::from(status)),
+        ("expected", expected.map_or(Json::Null, Json::from)),
+    ])
+}
+
+/// The outcome of one part, as shown to the user.
+pub(crate) struct PartResult<'a> {
+    pub(crate) day: u8,
+    pub(crate) part: Part,
+    pub(crate) title: &'a str,
+    pub(crate) answer: Result<&'a Answer, String>,
+    pub(crate) duration: Option<Duration>,
+    pub(crate) verdict: Option<Verdict>,
+    pub(crate) recorded: bool,
+}
+
+// Prints results either as prose or as one JSON object per day and part. JSON output is
+// collected into a single array, NDJSON is written as soon as a part is done.
+pub(crate) struct Reporter {
+    format: Format,
+    time: bool,
+    collected: Vec<Json>,
+}
+
+impl Reporter {
+    pub(crate) fn new(format: Format, time: bool) -> Self {
+        Self {
+            format,
+            time,
+            collected: vec![],
+        }
+    }
+
+    fn emit(&mut self, json: Json) {
+        match self.format {
+            Format::Text => {}
+            Format::Json => self.collected.push(json),
+            Format::Ndjson => println!("{json}"),
+        }
+    }
+
+    pub(crate) fn parsed(&mut self, day: u8, duration: Duration) {
+        if self.format == Format::Text && self.time {
+            println!("Day {day}: Parsing took {duration:.1?}");
+        }
+    }
+
+    pub(crate) fn part(&mut self, result: PartResult<'_>) {
+        if self.format == Format::Text {
+            let answer = match &result.answer {
+                Ok(answer) => answer,
+                // failures are reported on stderr
+                Err(_) => return,
+            };
+            let mut line = format!("Day {}: {} = {answer}", result.day, result.title);
+            if let (true, Some(duration)) = (self.time, result.duration) {
+                line += &format!(" ({duration:.1?})");
+            }
+            match &result.verdict {
+                Some(_) if result.recorded => line += " [recorded]",
+                Some(Verdict::Correct) => line += " [correct]",
+                Some(Verdict::Wrong(expected)) => line += &format!(" [wrong, expected {expected}]"),
+                Some(Verdict::Unknown) => line += " [unknown]",
+                None => {}
+            }
+            println!("{line}");
+            return;
+        }
+        let mut fields = vec![
+            ("day", Json::Integer(result.day.into())),
+            ("part", Json::Integer(result.part.number().into())),
+            ("title", Json::from(result.title)),
+            ("answer", result.answer.as_ref().map_or(Json::Null, |answer| Json::from(*answer))),
+            ("duration_ns", result.duration.map_or(Json::Null, nanos)),
+            ("error", result.answer.as_ref().err().map_or(Json::Null, |e| Json::from(e.as_str()))),
+        ];
+        if let Some(verdict) = &result.verdict {
+            fields.push(("check", verdict_json(verdict)));
+            fields.push(("recorded", Json::Bool(result.recorded)));
+        }
+        self.emit(Json::object(fields));
+    }
+
+    pub(crate) fn samples(&mut self, day: u8, phase: &str, samples: &Samples) {
+        if self.format == Format::Text {
+            println!("Day {day} {phase:<6}: min {:>10.1?}  median {:>10.1?}  p99 {:>10.1?}  ({} runs)",
+                     samples.min(), samples.median(), samples.percentile(99.0), samples.len());
+            return;
+        }
+        self.emit(Json::object([
+            ("day", Json::Integer(day.into())),
+            ("phase", Json::from(phase)),
+            ("runs", Json::Integer(samples.len() as u64)),
+            ("min_ns", nanos(samples.min())),
+            ("median_ns", nanos(samples.median())),
+            ("p99_ns", nanos(samples.percentile(99.0))),
+        ]));
+    }
+
+    pub(crate) fn day_done(&mut self) {
+        if self.format == Format::Text {
+            println!();
+        }
+    }
+
+    pub(crate) fn finish(self) {
+        if self.format == Format::Json {
+            println!("{}", Json::Array(self.collected));
+        }
+    }
+}
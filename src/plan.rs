@@ -0,0 +1,67 @@
+use aoc23::day3::{Layout, Move};
+use aoc23::json::Json;
+
+use crate::cli::Args;
+use crate::output::Format;
+
+fn moves_text(moves: &[Move]) -> String {
+    if moves.is_empty() {
+        return "none".to_string();
+    }
+    moves.iter()
+        .map(Move::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn layout_json(layout: &Layout) -> Json {
+    let moves = layout.moves.iter()
+        .map(|m| Json::object([
+            ("item", Json::String(m.item.to_string())),
+            ("count", Json::Integer(m.count as u64)),
+            ("from", Json::from(m.from.name())),
+        ]))
+        .collect();
+    Json::object([
+        ("line", Json::Integer(layout.line as u64)),
+        ("left", Json::String(layout.left.clone())),
+        ("right", Json::String(layout.right.clone())),
+        ("moves", Json::Array(moves)),
+        ("cost", Json::Integer(layout.cost().into())),
+    ])
+}
+
+fn print_csv(layouts: &[Layout]) {
+    println!("line,left,right,moves,cost");
+    for layout in layouts {
+        println!("{},{},{},{},{}", layout.line, layout.left, layout.right, layout.move_count(), layout.cost());
+    }
+}
+
+fn print_table(layouts: &[Layout]) {
+    println!("{:>6} {:>6} {:>6}  Layout", "Line", "Moves", "Cost");
+    for layout in layouts {
+        println!("{:>6} {:>6} {:>6}  {layout}", layout.line, layout.move_count(), layout.cost());
+        println!("{:>22}moved {}", "", moves_text(&layout.moves));
+    }
+    println!();
+    println!("Rucksacks: {}", layouts.len());
+    println!("Moves:     {}", layouts.iter().map(Layout::move_count).sum::<usize>());
+    println!("Cost:      {}", layouts.iter().map(Layout::cost).sum::<u32>());
+}
+
+// Plans the reorganization of the day 3 rucksacks, as a table of layouts and moves, CSV or JSON
+pub(crate) fn print(layouts: &[Layout], args: &Args) {
+    match args.format {
+        _ if args.csv => print_csv(layouts),
+        Format::Text => print_table(layouts),
+        Format::Json | Format::Ndjson => {
+            let json = Json::object([
+                ("layouts", Json::Array(layouts.iter().map(layout_json).collect())),
+                ("moves", Json::Integer(layouts.iter().map(Layout::move_count).sum::<usize>() as u64)),
+                ("cost", Json::Integer(layouts.iter().map(Layout::cost).sum::<u32>().into())),
+            ]);
+            println!("{json}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Runs `work` on every job with up to `threads` scoped workers and hands each result to `done`
+/// on the calling thread, in job order no matter which job finishes first.
+pub fn run_ordered<T, R, W, D>(jobs: &[T], threads: usize, work: W, mut done: D)
+    where T: Sync, R: Send, W: Fn(&T) -> R + Sync, D: FnMut(&T, R) {
+    if threads <= 1 {
+        for job in jobs {
+            done(job, work(job));
+        }
+        return;
+    }
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            let sender = sender.clone();
+            let (next, work) = (&next, &work);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(i) else {
+                    break;
+                };
+                if sender.send((i, work(job))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        // results that finished before one of their predecessors wait here
+        let mut pending = BTreeMap::new();
+        let mut emitted = 0;
+        for (i, result) in receiver {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&emitted) {
+                done(&jobs[emitted], result);
+                emitted += 1;
+            }
+        }
+    });
+}
+
+pub fn map<T, R, W>(jobs: &[T], threads: usize, work: W) -> Vec<R>
+    where T: Sync, R: Send, W: Fn(&T) -> R + Sync {
+    let mut results = Vec::with_capacity(jobs.len());
+    run_ordered(jobs, threads, work, |_, result| results.push(result));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+    use super::{map, run_ordered};
+
+    #[test]
+    fn test_order() {
+        // later jobs finish first
+        let jobs = [40, 30, 20, 10, 0];
+        let results = map(&jobs, 5, |millis| {
+            thread::sleep(Duration::from_millis(*millis));
+            millis / 10
+        });
+        assert_eq!(results, vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_done() {
+        let jobs = (1..=100).collect::<Vec<u32>>();
+        let mut seen = vec![];
+        run_ordered(&jobs, 3, |n| n * n, |n, square| seen.push((*n, square)));
+        assert_eq!(seen, jobs.iter().map(|n| (*n, n * n)).collect::<Vec<(u32, u32)>>());
+        assert!(map(&[] as &[u32], 4, |n| *n).is_empty());
+    }
+}
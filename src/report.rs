@@ -0,0 +1,59 @@
+use aoc23::day1::Inventory;
+
+use crate::cli::Args;
+
+// characters of the longest histogram bar
+const BAR_WIDTH: usize = 50;
+
+fn print_csv(inventory: &Inventory) {
+    println!("rank,elf,items,calories");
+    for (rank, elf) in inventory.ranked().iter().enumerate() {
+        println!("{},{},{},{}", rank + 1, elf.index(), elf.items().len(), elf.total());
+    }
+}
+
+fn print_table(inventory: &Inventory, args: &Args) {
+    println!("{:>5} {:>5} {:>6} {:>9}", "Rank", "Elf", "Items", "Calories");
+    for (rank, elf) in inventory.ranked().iter().enumerate() {
+        println!("{:>5} {:>5} {:>6} {:>9}", rank + 1, elf.index(), elf.items().len(), elf.total());
+    }
+    println!();
+    println!("Elves:  {}", inventory.elves().len());
+    if let (Some(mean), Some(median)) = (inventory.mean(), inventory.median()) {
+        println!("Mean:   {mean:.1}");
+        println!("Median: {median:.1}");
+    }
+    for p in [90.0, 99.0] {
+        if let Some(calories) = inventory.percentile(p) {
+            println!("p{p}:    {calories}");
+        }
+    }
+    for tie in inventory.ties() {
+        let elves = tie.iter()
+            .map(|elf| elf.index().to_string())
+            .collect::<Vec<String>>();
+        println!("Tied at {} calories: elves {}", tie[0].total(), elves.join(", "));
+    }
+    let histogram = inventory.histogram(args.bucket);
+    if !histogram.is_empty() {
+        println!("Histogram ({} calories per bucket):", args.bucket);
+        let largest = histogram.iter().map(|bucket| bucket.count).max().unwrap_or(1);
+        for bucket in histogram {
+            let end = u64::from(bucket.start) + u64::from(args.bucket.get());
+            let bar = (bucket.count * BAR_WIDTH).div_ceil(largest);
+            println!("{:>9}..{:<9} {:>5} {}", bucket.start, end, bucket.count, "#".repeat(bar));
+        }
+    }
+    if let Some(calories) = args.above {
+        println!("More than {calories} calories: {} elves", inventory.count_above(calories));
+    }
+}
+
+// Ranks the elves of day 1 either as a table followed by statistics or as CSV
+pub(crate) fn print(inventory: &Inventory, args: &Args) {
+    if args.csv {
+        print_csv(inventory);
+    } else {
+        print_table(inventory, args);
+    }
+}
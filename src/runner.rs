@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::lines::LineSource;
+use crate::pool;
+use crate::solution::{Answer, Part, Puzzle};
+use crate::timing::timed;
+
+#[derive(Debug)]
+pub struct PartReport {
+    pub part: Part,
+    pub title: &'static str,
+    pub answer: Result<Answer>,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct DayReport {
+    pub day: u8,
+    pub parse: Duration,
+    pub parts: Vec<PartReport>,
+}
+
+// Parses the input once and solves the requested parts on it, timing every phase; with
+// `parallel` every part gets a thread of its own. A parse error fails the whole day, while
+// a failing part is recorded in its report.
+pub fn run(day: u8, puzzle: &dyn Puzzle, lines: &mut dyn LineSource, parts: &[Part], parallel: bool) -> Result<DayReport> {
+    let (input, parse) = timed(|| puzzle.parse(lines));
+    let input = input?;
+    let solve = |part: &Part| {
+        let (answer, duration) = timed(|| puzzle.solve(input.as_ref(), *part));
+        PartReport {
+            part: *part,
+            title: puzzle.title(*part),
+            answer,
+            duration,
+        }
+    };
+    let parts = if parallel {
+        pool::map(parts, parts.len(), solve)
+    } else {
+        parts.iter().map(solve).collect()
+    };
+    Ok(DayReport {
+        day,
+        parse,
+        parts,
+    })
+}
+
+// Like `run`, but solves days that support it in a single pass over the input. The pass is
+// reported as the parse phase, since reading dominates it; any error fails the whole day.
+pub fn stream(day: u8, puzzle: &dyn Puzzle, lines: &mut dyn LineSource, parts: &[Part], parallel: bool) -> Result<DayReport> {
+    let (answers, parse) = timed(|| puzzle.stream(lines));
+    let (first, second) = match answers {
+        Some(answers) => answers?,
+        None => return run(day, puzzle, lines, parts, parallel),
+    };
+    let parts = parts.iter()
+        .map(|part| PartReport {
+            part: *part,
+            title: puzzle.title(*part),
+            answer: Ok(match part {
+                Part::One => first.clone(),
+                Part::Two => second.clone(),
+            }),
+            duration: Duration::ZERO,
+        })
+        .collect();
+    Ok(DayReport {
+        day,
+        parse,
+        parts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lines::SliceLines;
+    use crate::solution::{Answer, Part, Registry};
+    use super::{run, stream};
+
+    #[test]
+    fn test_run() {
+        let registry = Registry::default();
+        let lines = ["A Y", "B X", "C Z"].map(str::to_string);
+        let report = run(2, registry.get(2).unwrap(), &mut SliceLines::new(&lines), &[Part::Two], false).unwrap();
+        assert_eq!(report.day, 2);
+        assert_eq!(report.parts.len(), 1);
+        assert_eq!(report.parts[0].part, Part::Two);
+        assert_eq!(report.parts[0].answer.as_ref().unwrap(), &Answer::Number(12));
+    }
+
+    #[test]
+    fn test_run_parallel() {
+        let registry = Registry::default();
+        let lines = ["2-4,6-8", "2-8,3-7", "6-6,4-6", "2-6,4-8"].map(str::to_string);
+        let report = run(4, registry.get(4).unwrap(), &mut SliceLines::new(&lines), &[Part::Two, Part::One], true).unwrap();
+        let parts = report.parts.iter()
+            .map(|part| (part.part, part.answer.as_ref().unwrap().clone()))
+            .collect::<Vec<(Part, Answer)>>();
+        assert_eq!(parts, vec![(Part::Two, Answer::Number(3)), (Part::One, Answer::Number(2))]);
+    }
+
+    #[test]
+    fn test_stream() {
+        let registry = Registry::default();
+        let lines = ["A Y", "B X", "C Z"].map(str::to_string);
+        let report = stream(2, registry.get(2).unwrap(), &mut SliceLines::new(&lines), &[Part::One, Part::Two], false).unwrap();
+        let answers = report.parts.iter()
+            .map(|part| part.answer.as_ref().unwrap().clone())
+            .collect::<Vec<Answer>>();
+        assert_eq!(answers, vec![Answer::Number(15), Answer::Number(12)]);
+        // days without a single pass solution fall back to parsing
+        let lines = ["mjqjpqmgbljsphdztnvjfqwrcgsmlb"].map(str::to_string);
+        let report = stream(6, registry.get(6).unwrap(), &mut SliceLines::new(&lines), &[Part::One], true).unwrap();
+        assert_eq!(report.parts[0].answer.as_ref().unwrap(), &Answer::List(vec![Answer::Number(7)]));
+    }
+}
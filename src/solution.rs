@@ -0,0 +1,199 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{day1, day2, day3, day4, day5, day6, day7};
+use crate::error::Result;
+use crate::lines::LineSource;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub fn number(self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "part {}", self.number())
+    }
+}
+
+/// The result of one part of a puzzle, kept typed so it can be compared and reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(u64),
+    Text(String),
+    List(Vec<Answer>),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{n}"),
+            Answer::Text(s) => write!(f, "{s}"),
+            Answer::List(answers) => {
+                let answers = answers.iter()
+                    .map(Answer::to_string)
+                    .collect::<Vec<String>>();
+                write!(f, "{}", answers.join(", "))
+            }
+        }
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Self {
+        Answer::Number(n.into())
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Number(n as u64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl<T> From<Vec<T>> for Answer
+    where T: Into<Answer> {
+    fn from(items: Vec<T>) -> Self {
+        Answer::List(items.into_iter().map(T::into).collect())
+    }
+}
+
+/// A single day of the calendar: the input is parsed once and shared by both parts.
+pub trait Solution {
+    type Input;
+
+    const PART1: &'static str;
+    const PART2: &'static str;
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Self::Input>;
+    fn part1(&self, input: &Self::Input) -> Result<Answer>;
+    fn part2(&self, input: &Self::Input) -> Result<Answer>;
+
+    /// Solves both parts in a single pass, for days whose answers fold line by line and can
+    /// therefore handle inputs larger than memory. Days without one return `None` before reading.
+    fn stream(&self, _lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>> {
+        None
+    }
+}
+
+/// Object safe view of a [`Solution`], so days with different input types can live in one [`Registry`].
+pub trait Puzzle: Send + Sync {
+    fn title(&self, part: Part) -> &'static str;
+    // Inputs are shared between threads when the parts run in parallel
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Box<dyn Any + Send + Sync>>;
+    fn solve(&self, input: &(dyn Any + Send + Sync), part: Part) -> Result<Answer>;
+    fn stream(&self, lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>>;
+}
+
+impl<S> Puzzle for S
+    where S: Solution + Send + Sync,
+          S::Input: Send + Sync + 'static {
+    fn title(&self, part: Part) -> &'static str {
+        match part {
+            Part::One => S::PART1,
+            Part::Two => S::PART2,
+        }
+    }
+
+    fn parse(&self, lines: &mut dyn LineSource) -> Result<Box<dyn Any + Send + Sync>> {
+        Ok(Box::new(Solution::parse(self, lines)?))
+    }
+
+    fn solve(&self, input: &(dyn Any + Send + Sync), part: Part) -> Result<Answer> {
+        let input = input.downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        match part {
+            Part::One => self.part1(input),
+            Part::Two => self.part2(input),
+        }
+    }
+
+    fn stream(&self, lines: &mut dyn LineSource) -> Option<Result<(Answer, Answer)>> {
+        Solution::stream(self, lines)
+    }
+}
+
+pub struct Registry {
+    days: BTreeMap<u8, Box<dyn Puzzle>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            days: BTreeMap::new(),
+        }
+    }
+
+    pub fn register<S>(&mut self, day: u8, solution: S)
+        where S: Puzzle + 'static {
+        self.days.insert(day, Box::new(solution));
+    }
+
+    pub fn get(&self, day: u8) -> Option<&dyn Puzzle> {
+        self.days.get(&day).map(Box::as_ref)
+    }
+
+    pub fn days(&self) -> impl Iterator<Item=u8> + '_ {
+        self.days.keys().copied()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(1, day1::Day1);
+        registry.register(2, day2::Day2::default());
+        registry.register(3, day3::Day3::default());
+        registry.register(4, day4::Day4);
+        registry.register(5, day5::Day5);
+        registry.register(6, day6::Day6);
+        registry.register(7, day7::Day7);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lines::SliceLines;
+    use super::{Answer, Part, Registry};
+
+    #[test]
+    fn test_part() {
+        assert_eq!((Part::One.number(), Part::Two.number()), (1, 2));
+        assert_eq!(Part::Two.to_string(), "part 2");
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = Registry::default();
+        assert_eq!(registry.days().collect::<Vec<u8>>(), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert!(registry.get(8).is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let registry = Registry::default();
+        let day1 = registry.get(1).unwrap();
+        let lines = ["1000", "2000", "", "4000", "", "500"].map(str::to_string);
+        let input = day1.parse(&mut SliceLines::new(&lines)).unwrap();
+        assert_eq!(day1.solve(input.as_ref(), Part::One).unwrap(), Answer::Number(4000));
+        assert_eq!(day1.solve(input.as_ref(), Part::Two).unwrap(), Answer::Number(7500));
+    }
+}
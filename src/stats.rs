@@ -0,0 +1,22 @@
+// Nearest rank percentile of values sorted in ascending order, p is given in 0..=100.
+// None for no values
+pub fn percentile<T>(sorted: &[T], p: f64) -> Option<T>
+    where T: Copy {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percentile;
+
+    #[test]
+    fn test_percentile() {
+        let sorted = (1..=10).collect::<Vec<u32>>();
+        assert_eq!(percentile(&sorted, 0.0), Some(1));
+        assert_eq!(percentile(&sorted, 25.0), Some(3));
+        assert_eq!(percentile(&sorted, 50.0), Some(5));
+        assert_eq!(percentile(&sorted, 100.0), Some(10));
+        assert_eq!(percentile::<u32>(&[], 50.0), None);
+    }
+}
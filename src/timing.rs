@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::lines::SliceLines;
+use crate::solution::{Part, Puzzle};
+use crate::stats;
+
+pub fn timed<T, F>(f: F) -> (T, Duration)
+    where F: FnOnce() -> T {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Wall times of repeated runs of one phase, kept sorted for order statistics.
+#[derive(Debug, Clone)]
+pub struct Samples {
+    sorted: Vec<Duration>,
+}
+
+impl Samples {
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        Self {
+            sorted: samples,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.sorted.first().copied().unwrap_or_default()
+    }
+
+    pub fn median(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    // Zero without samples
+    pub fn percentile(&self, p: f64) -> Duration {
+        stats::percentile(&self.sorted, p).unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub struct Bench {
+    pub parse: Samples,
+    pub parts: Vec<(Part, Samples)>,
+}
+
+// Repeats every phase of a day `runs` times; each part is solved on the input of the last parse.
+pub fn bench(puzzle: &dyn Puzzle, lines: &[String], parts: &[Part], runs: usize) -> Result<Bench> {
+    let mut parse = vec![];
+    let mut input = None;
+    for _ in 0..runs {
+        let (parsed, duration) = timed(|| puzzle.parse(&mut SliceLines::new(lines)));
+        parse.push(duration);
+        input = Some(parsed?);
+    }
+    let mut part_samples = vec![];
+    if let Some(input) = input {
+        for part in parts {
+            let mut samples = vec![];
+            for _ in 0..runs {
+                let (answer, duration) = timed(|| puzzle.solve(input.as_ref(), *part));
+                answer?;
+                samples.push(duration);
+            }
+            part_samples.push((*part, Samples::new(samples)));
+        }
+    }
+    Ok(Bench {
+        parse: Samples::new(parse),
+        parts: part_samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::solution::{Part, Registry};
+    use super::{bench, Samples};
+
+    #[test]
+    fn test_samples() {
+        let samples = Samples::new((1..=100).rev().map(Duration::from_millis).collect());
+        assert_eq!(samples.len(), 100);
+        assert_eq!(samples.min(), Duration::from_millis(1));
+        assert_eq!(samples.median(), Duration::from_millis(50));
+        assert_eq!(samples.percentile(99.0), Duration::from_millis(99));
+        assert_eq!(Samples::new(vec![]).median(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_bench() {
+        let registry = Registry::default();
+        let lines = ["2-4,6-8", "2-3,4-5"].map(str::to_string);
+        let result = bench(registry.get(4).unwrap(), &lines, &[Part::One, Part::Two], 5).unwrap();
+        assert_eq!(result.parse.len(), 5);
+        assert_eq!(result.parts.len(), 2);
+        assert!(result.parts.iter().all(|(_, samples)| samples.len() == 5));
+    }
+}
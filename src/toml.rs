@@ -0,0 +1,219 @@
+use std::fmt;
+
+use crate::error::{Error, Result};
+
+/// Values of the small TOML subset used by the answers and rules files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Integer(u64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    /// One based position of the key.
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A `[name]` section, or the entries before the first section when `name` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: Option<String>,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+// Parses `key = value` lines grouped into `[name]` sections, with `#` comments. The first
+// table holds the entries before any section and is always present.
+pub fn parse(text: &str) -> Result<Vec<Table>> {
+    let mut tables = vec![Table { name: None, line: 1, entries: vec![] }];
+    for (i, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim_end();
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim_start();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            let name = section.strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| is_key(name))
+                .ok_or_else(|| Error::parse(format!("Expected a section like [name], found '{line}'")).at(i + 1, indent + 1))?;
+            tables.push(Table { name: Some(name.to_string()), line: i + 1, entries: vec![] });
+            continue;
+        }
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| Error::parse("Expected 'key = value'").at(i + 1, indent + 1))?;
+        if !is_key(key.trim()) {
+            return Err(Error::parse(format!("Invalid key '{}'", key.trim())).at(i + 1, indent + 1));
+        }
+        let column = indent + key.len() + 2;
+        let mut parser = ValueParser { input: value, pos: 0 };
+        let value = parser.value()
+            .and_then(|value| parser.end().map(|_| value))
+            .map_err(|(pos, reason)| Error::parse(reason).at(i + 1, column + pos))?;
+        let table = tables.last_mut().expect("The root table is always present");
+        table.entries.push(Entry {
+            key: key.trim().to_string(),
+            value,
+            line: i + 1,
+            column: indent + 1,
+        });
+    }
+    Ok(tables)
+}
+
+fn is_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// Parses the value side of `key = value`; errors carry the byte offset into the value
+struct ValueParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl ValueParser<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn value(&mut self) -> std::result::Result<Value, (usize, String)> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string(),
+            Some('[') => self.array(),
+            Some(c) if c.is_ascii_digit() => self.integer(),
+            Some(c) => Err((self.pos, format!("Unexpected '{c}', expected a number, string or list"))),
+            None => Err((self.pos, "Missing value".to_string())),
+        }
+    }
+
+    fn integer(&mut self) -> std::result::Result<Value, (usize, String)> {
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let len = rest.find(|c: char| !c.is_ascii_digit() && c != '_').unwrap_or(rest.len());
+        self.pos += len;
+        rest[..len].replace('_', "").parse()
+            .map(Value::Integer)
+            .map_err(|e| (start, format!("Invalid number: {e}")))
+    }
+
+    fn string(&mut self) -> std::result::Result<Value, (usize, String)> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut s = String::new();
+        let mut chars = self.input[self.pos..].chars();
+        while let Some(c) = chars.next() {
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(Value::String(s)),
+                '\\' => {
+                    let escaped = chars.next().ok_or((self.pos, "Unterminated escape".to_string()))?;
+                    self.pos += escaped.len_utf8();
+                    match escaped {
+                        '"' | '\\' => s.push(escaped),
+                        'n' => s.push('\n'),
+                        _ => return Err((self.pos - 2, format!("Unsupported escape '\\{escaped}'"))),
+                    }
+                }
+                _ => s.push(c),
+            }
+        }
+        Err((start, "Unterminated string".to_string()))
+    }
+
+    fn array(&mut self) -> std::result::Result<Value, (usize, String)> {
+        self.pos += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err((self.pos, "Expected ',' or ']'".to_string())),
+            }
+        }
+    }
+
+    fn end(&mut self) -> std::result::Result<(), (usize, String)> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(c) => Err((self.pos, format!("Unexpected '{c}' after value"))),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::String(s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")),
+            Value::Array(items) => {
+                let items = items.iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<String>>();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Value};
+
+    #[test]
+    fn test_parse() {
+        let tables = parse("top = 1\n[ rules ] # comment\n  win = [\"a#\", [2_0]]\n").unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].entries[0].value, Value::Integer(1));
+        assert_eq!((tables[1].name.as_deref(), tables[1].line), (Some("rules"), 2));
+        let entry = &tables[1].entries[0];
+        assert_eq!((entry.key.as_str(), entry.line, entry.column), ("win", 3, 3));
+        let value = Value::Array(vec![Value::String("a#".to_string()), Value::Array(vec![Value::Integer(20)])]);
+        assert_eq!(entry.value, value);
+        assert_eq!(value.to_string(), "[\"a#\", [20]]");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse("a = 12 3").unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(8)));
+        let error = parse("\n[a b]").unwrap_err();
+        assert_eq!(error.line(), Some(2));
+        let error = parse("x y = 1").unwrap_err();
+        assert_eq!(error.line(), Some(1));
+    }
+}
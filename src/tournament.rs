@@ -0,0 +1,140 @@
+use aoc23::day2::{Play, RoundComparison, Rules};
+use aoc23::error::Result;
+use aoc23::json::Json;
+
+use crate::cli::Args;
+use crate::output::Format;
+
+fn move_name(rules: &Rules, played: usize) -> &str {
+    &rules.game().moves()[played]
+}
+
+fn round_json(rules: &Rules, round: &RoundComparison) -> Json {
+    let play = |play: &Play| Json::object([
+        ("move", Json::from(move_name(rules, play.played))),
+        ("outcome", Json::from(play.outcome.name())),
+        ("score", Json::Integer(play.score.into())),
+    ]);
+    Json::object([
+        ("round", Json::Integer(round.line as u64)),
+        ("opponent", Json::from(move_name(rules, round.opponent))),
+        ("part1", play(&round.first)),
+        ("part2", play(&round.second)),
+        ("best", play(&round.best)),
+    ])
+}
+
+// Per round scores of both readings of the guide, their totals and how many rounds each wins
+struct Summary {
+    totals: [u64; 3],
+    wins: [usize; 2],
+    ties: usize,
+}
+
+fn summarize(rounds: &[RoundComparison]) -> Summary {
+    let mut summary = Summary { totals: [0; 3], wins: [0; 2], ties: 0 };
+    for round in rounds {
+        summary.totals[0] += u64::from(round.first.score);
+        summary.totals[1] += u64::from(round.second.score);
+        summary.totals[2] += u64::from(round.best.score);
+        match round.first.score.cmp(&round.second.score) {
+            std::cmp::Ordering::Greater => summary.wins[0] += 1,
+            std::cmp::Ordering::Less => summary.wins[1] += 1,
+            std::cmp::Ordering::Equal => summary.ties += 1,
+        }
+    }
+    summary
+}
+
+// Expected score per round of always playing each move against the given opponent
+fn expected(rules: &Rules, weights: &[f64]) -> Result<Vec<f64>> {
+    (0..rules.game().moves().len())
+        .map(|played| rules.expected_score(played, weights))
+        .collect()
+}
+
+fn summary_json(rules: &Rules, summary: &Summary, distributions: &[(&str, Vec<f64>)]) -> Result<Json> {
+    let mut strategies = vec![];
+    for (name, weights) in distributions {
+        let (best, score) = rules.best_response(weights)?;
+        let expected = expected(rules, weights)?.into_iter()
+            .enumerate()
+            .map(|(played, score)| (move_name(rules, played).to_string(), Json::Float(score)));
+        strategies.push(Json::object([
+            ("opponent", Json::from(*name)),
+            ("expected", Json::object(expected)),
+            ("best_move", Json::from(move_name(rules, best))),
+            ("best_score", Json::Float(score)),
+        ]));
+    }
+    Ok(Json::object([
+        ("total1", Json::Integer(summary.totals[0])),
+        ("total2", Json::Integer(summary.totals[1])),
+        ("best_total", Json::Integer(summary.totals[2])),
+        ("wins1", Json::Integer(summary.wins[0] as u64)),
+        ("wins2", Json::Integer(summary.wins[1] as u64)),
+        ("ties", Json::Integer(summary.ties as u64)),
+        ("strategies", Json::Array(strategies)),
+    ]))
+}
+
+fn print_csv(rules: &Rules, rounds: &[RoundComparison]) {
+    println!("round,opponent,move1,outcome1,score1,move2,outcome2,score2,best_move,best_score");
+    for round in rounds {
+        println!("{},{},{},{},{},{},{},{},{},{}",
+                 round.line, move_name(rules, round.opponent),
+                 move_name(rules, round.first.played), round.first.outcome.name(), round.first.score,
+                 move_name(rules, round.second.played), round.second.outcome.name(), round.second.score,
+                 move_name(rules, round.best.played), round.best.score);
+    }
+}
+
+fn print_table(rules: &Rules, rounds: &[RoundComparison], summary: &Summary, distributions: &[(&str, Vec<f64>)]) -> Result<()> {
+    println!("{:>6} {:>10} {:>18} {:>18} {:>14}", "Round", "Opponent", "Part 1", "Part 2", "Best");
+    let cell = |play: &Play| format!("{} {} {}", move_name(rules, play.played), play.outcome.name(), play.score);
+    for round in rounds {
+        println!("{:>6} {:>10} {:>18} {:>18} {:>14}", round.line, move_name(rules, round.opponent),
+                 cell(&round.first), cell(&round.second),
+                 format!("{} {}", move_name(rules, round.best.played), round.best.score));
+    }
+    println!();
+    println!("Part 1 total: {}", summary.totals[0]);
+    println!("Part 2 total: {}", summary.totals[1]);
+    println!("Best total:   {}", summary.totals[2]);
+    println!("Rounds won by part 1: {}, by part 2: {}, tied: {}", summary.wins[0], summary.wins[1], summary.ties);
+    for (name, weights) in distributions {
+        println!("Against the {name} opponent:");
+        for (played, score) in expected(rules, weights)?.into_iter().enumerate() {
+            println!("  {:<10} {score:.3}", move_name(rules, played));
+        }
+        let (best, score) = rules.best_response(weights)?;
+        println!("  best response: {} ({score:.3} per round)", move_name(rules, best));
+    }
+    Ok(())
+}
+
+// Compares both readings of the day 2 guide round by round, as a table, CSV or JSON
+pub(crate) fn print(rules: &Rules, lines: &[String], args: &Args) -> Result<()> {
+    let rounds = rules.compare(lines)?;
+    let summary = summarize(&rounds);
+    let mut distributions = vec![("uniform", vec![1.0; rules.game().moves().len()])];
+    if !rounds.is_empty() {
+        distributions.push(("observed", rules.opponent_distribution(lines)?));
+    }
+    match args.format {
+        _ if args.csv => print_csv(rules, &rounds),
+        Format::Text => print_table(rules, &rounds, &summary, &distributions)?,
+        Format::Json => {
+            let rounds = rounds.iter().map(|round| round_json(rules, round)).collect();
+            let summary = summary_json(rules, &summary, &distributions)?;
+            println!("{}", Json::object([("rounds", Json::Array(rounds)), ("summary", summary)]));
+        }
+        Format::Ndjson => {
+            for round in &rounds {
+                println!("{}", round_json(rules, round));
+            }
+            println!("{}", summary_json(rules, &summary, &distributions)?);
+        }
+    }
+    Ok(())
+}